@@ -0,0 +1,38 @@
+//! Lexes generated inputs of growing size and prints the throughput of
+//! each. The time per megabyte should stay flat as the input grows.
+//!
+//! Run with `cargo bench --bench lexer`.
+use lang::lexer::token::Lexer;
+use std::hint::black_box;
+use std::time::Instant;
+
+const SNIPPET: &str = "/// Adds two numbers
+func add|a: Number, b: Number|: Number {
+    ! total: Number = a + b * 0x2A - 1_000.5e-1
+    return `sum: ${total}` + \"done\\n\"
+}
+";
+
+fn source(bytes: usize) -> String {
+    SNIPPET.repeat(bytes / SNIPPET.len() + 1)
+}
+
+fn main() {
+    let mut base: Option<f64> = None;
+    for mb in [1, 2, 4, 8, 16] {
+        let input = source(mb * 1024 * 1024);
+        let start = Instant::now();
+        let count = Lexer::new(input).count();
+        let elapsed = start.elapsed().as_secs_f64();
+        let per_mb = elapsed / mb as f64;
+        let ratio = per_mb / *base.get_or_insert(per_mb);
+        println!(
+            "{:>3} MB: {:>9} tokens in {:>8.3} s ({:.3} s/MB, x{:.2} of 1 MB)",
+            mb,
+            black_box(count),
+            elapsed,
+            per_mb,
+            ratio
+        );
+    }
+}
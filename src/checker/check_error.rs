@@ -0,0 +1,61 @@
+use crate::lexer::token::Span;
+
+/// What a semantic check found wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CheckErrorKind {
+    UndefinedVariable,
+    AssignToConstant,
+    Redeclaration,
+    OutsideLoop,    // `break` or `continue` not in a loop
+    UndefinedLabel, // `break 'name` with no enclosing loop called `name`
+    BreakValue,     // `break value` out of a loop other than `loop`
+    ArgumentCount,  // More positional arguments than parameters
+    UnknownArgument,
+    DuplicateArgument,
+    MissingArgument,
+    NamedRestArgument, // A named argument for a rest parameter
+}
+
+impl CheckErrorKind {
+    /// Stable error code, shown in diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            CheckErrorKind::UndefinedVariable => "E0201",
+            CheckErrorKind::AssignToConstant => "E0202",
+            CheckErrorKind::Redeclaration => "E0203",
+            CheckErrorKind::OutsideLoop => "E0204",
+            CheckErrorKind::UndefinedLabel => "E0205",
+            CheckErrorKind::BreakValue => "E0206",
+            CheckErrorKind::ArgumentCount => "E0207",
+            CheckErrorKind::UnknownArgument => "E0208",
+            CheckErrorKind::DuplicateArgument => "E0209",
+            CheckErrorKind::MissingArgument => "E0210",
+            CheckErrorKind::NamedRestArgument => "E0211",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckError {
+    pub kind: CheckErrorKind,
+    pub message: String,
+    pub span: Span, // The name, call or keyword at fault
+}
+
+impl CheckError {
+    pub fn new(kind: CheckErrorKind, message: impl Into<String>, span: Span) -> Self {
+        CheckError {
+            kind,
+            message: message.into(),
+            span,
+        }
+    }
+}
+
+impl std::fmt::Display for CheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error[{}]: {}", self.kind.code(), self.message)
+    }
+}
+
+impl std::error::Error for CheckError {}
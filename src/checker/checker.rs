@@ -0,0 +1,332 @@
+use crate::checker::check_error::*;
+use crate::interpreter::arguments::{match_arguments, ArgumentError, ParamSpec};
+use crate::interpreter::builtins::is_builtin;
+use crate::lexer::token::Span;
+use crate::parser::expression_ast::Expr;
+use crate::parser::statement_ast::{Arity, Stmt};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+struct Symbol {
+    constant: bool,
+    signature: Option<Signature>, // For a function declared with `func`
+}
+
+// What calls to a function declared with `func` are checked against
+#[derive(Debug, Clone)]
+struct Signature {
+    parameters: Vec<ParamSpec>,
+    arity: Arity,
+}
+
+// A loop enclosing the code being checked
+#[derive(Debug, Clone)]
+struct Loop {
+    label: Option<String>,
+    has_value: bool, // Only `loop` can be left with `break value`
+}
+
+/// Static checks run on a parsed program before it is executed: every
+/// name must be declared, constants are never reassigned, a scope
+/// declares a name only once and `break`/`continue` are used in loops.
+#[derive(Default)]
+pub struct Checker {
+    scopes: Vec<HashMap<String, Symbol>>,
+    loops: Vec<Loop>,
+    pub errors: Vec<CheckError>,
+}
+
+impl Checker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check a program, returning every problem found.
+    pub fn check(mut self, program: &Stmt) -> Result<(), Vec<CheckError>> {
+        self.check_stmt(program);
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.errors)
+        }
+    }
+
+    fn error(&mut self, kind: CheckErrorKind, message: String, span: Span) {
+        self.errors.push(CheckError::new(kind, message, span));
+    }
+
+    fn declare(&mut self, name: &str, constant: bool, span: Span) {
+        self.declare_symbol(name, Symbol { constant, signature: None }, span);
+    }
+
+    fn declare_symbol(&mut self, name: &str, symbol: Symbol, span: Span) {
+        let scope = self.scopes.last_mut().expect("a scope is always open while checking");
+        if scope.insert(name.to_string(), symbol).is_some() {
+            self.error(CheckErrorKind::Redeclaration, format!("`{}` is already declared in this scope", name), span);
+        }
+    }
+
+    fn resolve(&self, name: &str) -> Option<&Symbol> {
+        self.scopes.iter().rev().find_map(|s| s.get(name))
+    }
+
+    // Match the arguments of a call to a function declared with `func`.
+    // How many arguments a spread list gives is only known when running.
+    fn check_arguments(&mut self, function: &str, args: &[Expr], span: Span) {
+        let Some(signature) = self.resolve(function).and_then(|s| s.signature.clone()) else {
+            return;
+        };
+        if args.iter().any(|a| matches!(a, Expr::SpreadExpr { .. })) {
+            return;
+        }
+        let named: Vec<String> = args.iter().filter_map(|a| match a {
+            Expr::Property { name, .. } => Some(name.clone()),
+            _ => None,
+        }).collect();
+        if let Err(errors) = match_arguments(&signature.parameters, signature.arity, args.len() - named.len(), &named) {
+            for err in errors {
+                let kind = match err {
+                    ArgumentError::TooMany { .. } => CheckErrorKind::ArgumentCount,
+                    ArgumentError::Unknown(_) => CheckErrorKind::UnknownArgument,
+                    ArgumentError::Duplicate(_) => CheckErrorKind::DuplicateArgument,
+                    ArgumentError::NamedRest(_) => CheckErrorKind::NamedRestArgument,
+                    ArgumentError::Missing { .. } => CheckErrorKind::MissingArgument,
+                };
+                self.error(kind, err.message(function), span);
+            }
+        }
+    }
+
+    fn check_loop(&mut self, label: &Option<String>, has_value: bool, body: &[Stmt]) {
+        self.loops.push(Loop { label: label.clone(), has_value });
+        self.check_body(body);
+        self.loops.pop();
+    }
+
+    // Check a `break` or `continue`, returning the loop it leaves
+    fn check_jump(&mut self, keyword: &str, label: &Option<String>, span: Span) -> Option<Loop> {
+        let target = match label {
+            Some(name) => self.loops.iter().rev().find(|l| l.label.as_ref() == Some(name)).cloned(),
+            None => self.loops.last().cloned(),
+        };
+        match (target, label) {
+            (Some(target), _) => return Some(target),
+            (None, Some(name)) if !self.loops.is_empty() => {
+                self.error(CheckErrorKind::UndefinedLabel, format!("No enclosing loop is labelled `'{}`", name), span);
+            }
+            (None, _) => {
+                self.error(CheckErrorKind::OutsideLoop, format!("`{}` can only be used in a loop", keyword), span);
+            }
+        }
+        None
+    }
+
+    fn check_function(&mut self, parameters: &[Stmt], body: &[Stmt]) {
+        // Loops around the declaration cannot be left from inside it
+        let loops = std::mem::take(&mut self.loops);
+        self.scopes.push(HashMap::new());
+        for param in parameters {
+            self.check_stmt(param);
+        }
+        self.check_body(body);
+        self.scopes.pop();
+        self.loops = loops;
+    }
+
+    fn check_body(&mut self, body: &[Stmt]) {
+        self.scopes.push(HashMap::new());
+        // Functions are visible in the whole block that declares them
+        for stmt in body {
+            if let Stmt::FunctionDecl { name, parameters, arity, span, .. } = stmt {
+                let signature = Signature { parameters: ParamSpec::of(parameters), arity: *arity };
+                self.declare_symbol(name, Symbol { constant: true, signature: Some(signature) }, *span);
+            }
+        }
+        for stmt in body {
+            self.check_stmt(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn check_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Program { body } | Stmt::Block { body } => self.check_body(body),
+            Stmt::Expression { expression } => self.check_expr(expression),
+            // A lambda only runs once the name is bound, so it may call itself
+            Stmt::VariableDecl { name, value, constant, span, .. } if matches!(value.as_ref(), Expr::Lambda { .. }) => {
+                self.declare(name, *constant, *span);
+                self.check_expr(value);
+            }
+            Stmt::VariableDecl { name, value, constant, span, .. } => {
+                self.check_expr(value);
+                self.declare(name, *constant, *span);
+            }
+            Stmt::FunctionDecl { parameters, body, .. } => {
+                self.check_function(parameters, body.as_deref().unwrap_or_default());
+            }
+            Stmt::Parameter { name, default, span, .. } => {
+                if let Some(default) = default {
+                    self.check_expr(default);
+                }
+                self.declare(name, false, *span);
+            }
+            Stmt::Error { .. } => (),
+        }
+    }
+
+    // Chains such as `a + b + c` or `a.b(c).d` nest to the left with no
+    // limit from the parser, so they are walked in a loop, innermost first
+    fn check_expr(&mut self, expr: &Expr) {
+        let mut chain = vec![expr];
+        while let Some(left) = chain.last().and_then(|e| left_operand(e)) {
+            chain.push(left);
+        }
+        for expr in chain.into_iter().rev() {
+            self.check_node(expr);
+        }
+    }
+
+    // Check `expr`, except for its left operand, see `left_operand`
+    fn check_node(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Identifier { value, span } if self.resolve(value).is_none() && !is_builtin(value) => {
+                self.error(CheckErrorKind::UndefinedVariable, format!("Undefined variable `{}`", value), *span);
+            }
+            Expr::AssignmentExpr { assigne, value } | Expr::CompoundAssignmentExpr { assigne, value, .. } => {
+                if let Expr::Identifier { value: name, span } = assigne.as_ref()
+                    && self.resolve(name).is_some_and(|s| s.constant)
+                {
+                    self.error(CheckErrorKind::AssignToConstant, format!("Cannot assign to the constant `{}`", name), *span);
+                }
+                self.check_expr(assigne);
+                self.check_expr(value);
+            }
+            Expr::BinaryExpr { right, .. } => self.check_expr(right),
+            Expr::UnaryExpr { right, .. } => self.check_expr(right),
+            Expr::TernaryExpr { condition, true_value, false_value } => {
+                self.check_expr(condition);
+                self.check_expr(true_value);
+                self.check_expr(false_value);
+            }
+            Expr::List { value, .. } => value.iter().for_each(|v| self.check_expr(v)),
+            Expr::Map { entries } => {
+                for (key, value) in entries {
+                    self.check_expr(key);
+                    self.check_expr(value);
+                }
+            }
+            Expr::Return { value } => self.check_expr(value),
+            Expr::ForExpr { var_names, sequence, body, label, span } => {
+                self.check_expr(sequence);
+                self.scopes.push(HashMap::new());
+                for name in var_names {
+                    self.declare(name, false, *span);
+                }
+                self.check_loop(label, false, body);
+                self.scopes.pop();
+            }
+            Expr::WhileExpr { condition, body, label } => {
+                self.check_expr(condition);
+                self.check_loop(label, false, body);
+            }
+            Expr::Lambda { parameters, body, .. } => self.check_function(parameters, body),
+            Expr::LoopExpr { body, label } => self.check_loop(label, true, body),
+            Expr::BreakExpr { label, value, span } => {
+                if let Some(value) = value {
+                    self.check_expr(value);
+                }
+                if let Some(target) = self.check_jump("break", label, *span)
+                    && !target.has_value
+                    && value.is_some()
+                {
+                    self.error(CheckErrorKind::BreakValue, "Only `loop` can be left with `break` and a value".to_string(), *span);
+                }
+            }
+            Expr::ContinueExpr { label, span } => {
+                self.check_jump("continue", label, *span);
+            }
+            Expr::RangeExpr { start, end } => {
+                self.check_expr(start);
+                self.check_expr(end);
+            }
+            Expr::IfExpr { body, else_branch } => {
+                for (condition, branch) in body {
+                    self.check_expr(condition);
+                    self.check_body(branch);
+                }
+                self.check_body(else_branch);
+            }
+            Expr::TemplateString { parts } => parts.iter().for_each(|p| self.check_expr(p)),
+            Expr::CallExpr { args, caller, span } | Expr::NCallExpr { args, caller, span } => {
+                if let Expr::Identifier { value, .. } = caller.as_ref() {
+                    self.check_arguments(value, args, *span);
+                }
+                args.iter().for_each(|a| self.check_expr(a));
+            }
+            Expr::Property { value, .. } | Expr::SpreadExpr { value } => self.check_expr(value),
+            // The property is a name on the object, not a variable
+            Expr::ComputedExpr { property, .. } => self.check_expr(property),
+            _ => (),
+        }
+    }
+}
+
+// The operand an expression chains onto, checked before the expression
+fn left_operand(expr: &Expr) -> Option<&Expr> {
+    match expr {
+        Expr::BinaryExpr { left, .. } => Some(left),
+        Expr::MembreExpr { member, .. } | Expr::ComputedExpr { member, .. } => Some(member),
+        Expr::CallExpr { caller, .. } | Expr::NCallExpr { caller, .. } => Some(caller),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    fn error_codes(source: &str) -> Vec<&'static str> {
+        match crate::check_program(source) {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors.iter().map(|e| e.code()).collect(),
+        }
+    }
+
+    #[test]
+    fn a_lambda_can_call_the_variable_it_initializes() {
+        assert_eq!(error_codes("?f: Any = lambda |n| n < 1 ? 0 : f(n - 1)\nf(3)\n"), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn other_initializers_cannot_use_their_variable() {
+        assert_eq!(error_codes("?x: Number = x + 1\n"), vec!["E0201"]);
+        assert_eq!(error_codes("?f: Any = [lambda || f]\n"), vec!["E0201"]);
+    }
+
+    #[test]
+    fn each_branch_of_an_if_is_a_scope() {
+        assert_eq!(error_codes("if true {\n    ?x: Number = 1\n} else {\n    ?x: Number = 2\n}\nprint(x)\n"), vec!["E0201"]);
+        assert_eq!(error_codes("if y { 1 } else if z { 2 }\n"), vec!["E0201", "E0201"]);
+    }
+
+    #[test]
+    fn loop_variables_are_local_to_the_loop() {
+        assert_eq!(error_codes("for k, v in {1: 2} {\n    print(k, v)\n}\nprint(k)\n"), vec!["E0201"]);
+        assert_eq!(error_codes("for k, k in {1: 2} { }\n"), vec!["E0203"]);
+    }
+
+    #[test]
+    fn jumps_must_target_an_enclosing_loop() {
+        assert_eq!(error_codes("'a: while true {\n    loop { break 'a }\n}\n"), Vec::<&str>::new());
+        assert_eq!(error_codes("'a: loop {\n    while true { break 'a 1 }\n}\n"), Vec::<&str>::new());
+        assert_eq!(error_codes("'a: while true { }\nwhile true { continue 'a }\n"), vec!["E0205"]);
+        assert_eq!(error_codes("while true {\n    ?f: Any = lambda || { break }\n}\n"), vec!["E0204"]);
+        assert_eq!(error_codes("loop {\n    while true { break 1 }\n}\n"), vec!["E0206"]);
+    }
+
+    #[test]
+    fn long_chains_are_checked_without_recursing() {
+        let sum = vec!["1"; 20_000].join(" + ");
+        assert!(crate::check_program(&format!("print({})\n", sum)).is_ok());
+        let chain = vec!["len"; 20_000].join(".");
+        assert!(crate::check_program(&format!("print({})\n", chain)).is_ok());
+    }
+}
@@ -0,0 +1,3 @@
+pub mod check_error;
+#[allow(clippy::module_inception)]
+pub mod checker;
@@ -0,0 +1,313 @@
+use crate::checker::check_error::{CheckError, CheckErrorKind};
+use crate::error::Error;
+use crate::interpreter::runtime_error::RuntimeError;
+use crate::lexer::lex_error::{LexError, LexErrorKind};
+use crate::lexer::token::{Span, TokenType};
+use crate::parser::parse_error::{ErrorCode, ParseError};
+
+/// A place in the source, with a short explanation shown next to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool, // Primary labels mark the error itself, others give context
+}
+
+/// An error ready to be shown to the user: what went wrong, where, and
+/// what may fix it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub code: &'static str,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Diagnostic {
+            code,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: None,
+        }
+    }
+
+    pub fn with_primary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label { span, message: message.into(), primary: true });
+        self
+    }
+
+    pub fn with_secondary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label { span, message: message.into(), primary: false });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+}
+
+impl From<&LexError> for Diagnostic {
+    fn from(err: &LexError) -> Self {
+        let diagnostic = Diagnostic::new(err.kind.code(), &err.message);
+        match err.kind {
+            LexErrorKind::UnknownCharacter => diagnostic.with_primary(err.span, "not valid here"),
+            LexErrorKind::UnterminatedString => diagnostic
+                .with_primary(err.span, "string starts here")
+                .with_help("add a closing `\"`"),
+            LexErrorKind::UnterminatedTemplate => diagnostic
+                .with_primary(err.span, "template starts here")
+                .with_help("add a closing backquote"),
+            LexErrorKind::UnterminatedComment => diagnostic
+                .with_primary(err.span, "comment starts here")
+                .with_note("block comments nest, so every `/*` needs its own `*/`"),
+            LexErrorKind::InvalidEscape => diagnostic
+                .with_primary(err.span, "invalid escape")
+                .with_note("valid escapes are \\n \\t \\r \\0 \\\" \\' \\\\ \\` \\$ and \\u{...}"),
+            LexErrorKind::InvalidNumber => diagnostic.with_primary(err.span, "invalid number"),
+        }
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(err: &ParseError) -> Self {
+        let label = match (err.expected, err.found) {
+            (_, TokenType::EOF) => "the input ends here".to_string(),
+            (Some(expected), _) => format!("expected {:?}", expected),
+            (None, found) => format!("unexpected {:?}", found),
+        };
+        let mut diagnostic = Diagnostic::new(err.code.as_str(), &err.message).with_primary(err.span, label);
+        for (span, label) in &err.labels {
+            diagnostic = diagnostic.with_secondary(*span, label);
+        }
+        match err.code {
+            ErrorCode::ExpectedType => diagnostic.with_help("a type is `Number`, `String`, `Boolean`, `Any`, `Null`, a list type such as `[Number]` or a type name"),
+            ErrorCode::Unsupported => diagnostic.with_note("this syntax is reserved but not implemented yet"),
+            ErrorCode::InvalidAssignment => diagnostic.with_help("only a variable, a property `a.b` or an index `a[i]` can be assigned to"),
+            _ => diagnostic,
+        }
+    }
+}
+
+impl From<&CheckError> for Diagnostic {
+    fn from(err: &CheckError) -> Self {
+        let label = match err.kind {
+            CheckErrorKind::UndefinedVariable => "not declared in this scope",
+            CheckErrorKind::AssignToConstant => "assigned here",
+            CheckErrorKind::Redeclaration => "declared again here",
+            CheckErrorKind::OutsideLoop => "not inside a loop",
+            CheckErrorKind::UndefinedLabel => "unknown label",
+            CheckErrorKind::BreakValue => "leaves a loop that has no value",
+            CheckErrorKind::ArgumentCount
+            | CheckErrorKind::UnknownArgument
+            | CheckErrorKind::DuplicateArgument
+            | CheckErrorKind::MissingArgument
+            | CheckErrorKind::NamedRestArgument => "in this call",
+        };
+        Diagnostic::new(err.kind.code(), &err.message).with_primary(err.span, label)
+    }
+}
+
+impl From<&RuntimeError> for Diagnostic {
+    fn from(err: &RuntimeError) -> Self {
+        let diagnostic = Diagnostic::new(RuntimeError::CODE, &err.message);
+        match err.span {
+            Some(span) => diagnostic.with_primary(span, "failed here"),
+            None => diagnostic,
+        }
+    }
+}
+
+impl From<&Error> for Diagnostic {
+    fn from(err: &Error) -> Self {
+        match err {
+            Error::Lex(err) => err.into(),
+            Error::Parse(err) => err.into(),
+            Error::Check(err) => err.into(),
+            Error::Runtime(err) => err.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::render::{ErrorFormat, Renderer};
+
+    // The first error of `errors`, rendered for humans against `source`
+    fn render(source: &str, errors: Vec<Error>) -> String {
+        let renderer = Renderer::new("test.lang", source);
+        renderer.render(&Diagnostic::from(&errors[0]), ErrorFormat::Human).trim_end().to_string()
+    }
+
+    fn check(source: &str) -> String {
+        render(source, crate::check_program(source).expect_err("the check fails"))
+    }
+
+    fn run(source: &str) -> String {
+        render(source, crate::run(source).expect_err("the program fails"))
+    }
+
+    #[test]
+    fn undefined_variable() {
+        assert_eq!(check("print(x)\n"), "\
+error[E0201]: Undefined variable `x`
+ --> test.lang:1:7
+  |
+1 | print(x)
+  |       ^ not declared in this scope");
+    }
+
+    #[test]
+    fn assign_to_constant() {
+        assert_eq!(check("!c: Number = 1\nc += 2\n"), "\
+error[E0202]: Cannot assign to the constant `c`
+ --> test.lang:2:1
+  |
+2 | c += 2
+  | ^ assigned here");
+    }
+
+    #[test]
+    fn redeclaration() {
+        assert_eq!(check("?a: Number = 1\n?a: Number = 2\n"), "\
+error[E0203]: `a` is already declared in this scope
+ --> test.lang:2:2
+  |
+2 | ?a: Number = 2
+  |  ^ declared again here");
+    }
+
+    #[test]
+    fn outside_loop() {
+        assert_eq!(check("while true {\n    print(1)\n}\ncontinue\n"), "\
+error[E0204]: `continue` can only be used in a loop
+ --> test.lang:4:1
+  |
+4 | continue
+  | ^^^^^^^^ not inside a loop");
+    }
+
+    #[test]
+    fn undefined_label() {
+        assert_eq!(check("while true {\n    break 'outer\n}\n"), "\
+error[E0205]: No enclosing loop is labelled `'outer`
+ --> test.lang:2:5
+  |
+2 |     break 'outer
+  |     ^^^^^ unknown label");
+    }
+
+    #[test]
+    fn break_value() {
+        assert_eq!(check("while true {\n    break 1\n}\n"), "\
+error[E0206]: Only `loop` can be left with `break` and a value
+ --> test.lang:2:5
+  |
+2 |     break 1
+  |     ^^^^^ leaves a loop that has no value");
+    }
+
+    #[test]
+    fn argument_count() {
+        assert_eq!(check("func g|a: Number| {}\ng(1, 2)\n"), "\
+error[E0207]: g() takes 1 argument but 2 were given
+ --> test.lang:2:1
+  |
+2 | g(1, 2)
+  | ^^^^^^^ in this call");
+    }
+
+    #[test]
+    fn unknown_argument() {
+        assert_eq!(check("func g|a: Number| {}\ng(1, b: 2)\n"), "\
+error[E0208]: g() has no parameter named `b`
+ --> test.lang:2:1
+  |
+2 | g(1, b: 2)
+  | ^^^^^^^^^^ in this call");
+    }
+
+    #[test]
+    fn duplicate_argument() {
+        assert_eq!(check("func g|a: Number| {}\ng(1, a: 2)\n"), "\
+error[E0209]: g() got the argument `a` more than once
+ --> test.lang:2:1
+  |
+2 | g(1, a: 2)
+  | ^^^^^^^^^^ in this call");
+    }
+
+    #[test]
+    fn missing_argument() {
+        assert_eq!(check("func g|a: Number, b: Number| {}\nprint(g(1))\n"), "\
+error[E0210]: g() is missing the argument `b`; it takes 2 arguments
+ --> test.lang:2:7
+  |
+2 | print(g(1))
+  |       ^^^^ in this call");
+    }
+
+    #[test]
+    fn named_rest_argument() {
+        assert_eq!(check("func g|...r: [Number]| {}\ng(r: [1])\n"), "\
+error[E0211]: `r` gathers the extra arguments of g() and cannot be given by name
+ --> test.lang:2:1
+  |
+2 | g(r: [1])
+  | ^^^^^^^^^ in this call");
+    }
+
+    #[test]
+    fn runtime_errors_point_at_the_innermost_place() {
+        assert_eq!(run("func f|x: Any| {\n    return x.a\n}\nf({})\n"), "\
+error[E0301]: The map has no key a
+ --> test.lang:2:14
+  |
+2 |     return x.a
+  |              ^ failed here");
+        assert_eq!(run("?l: Any = [1]\nprint(l[5])\n"), "\
+error[E0301]: Index 5 is out of range for a List of length 1
+ --> test.lang:2:7
+  |
+2 | print(l[5])
+  |       ^ failed here");
+    }
+
+    #[test]
+    fn runtime_errors_fall_back_on_the_call() {
+        assert_eq!(run("print(len(5))\n"), "\
+error[E0301]: len() does not accept a Number
+ --> test.lang:1:7
+  |
+1 | print(len(5))
+  |       ^^^^^^ failed here");
+    }
+
+    #[test]
+    fn runtime_errors_in_declarations() {
+        assert_eq!(run("?r: Any = 1..\"a\"\n"), "\
+error[E0301]: A range needs numbers but got a Number and a String
+ --> test.lang:1:2
+  |
+1 | ?r: Any = 1..\"a\"
+  |  ^ failed here");
+    }
+
+    #[test]
+    fn json_carries_the_line() {
+        let source = "print(x)\n";
+        let errors = crate::check_program(source).unwrap_err();
+        let json = Renderer::new("test.lang", source).render(&Diagnostic::from(&errors[0]), ErrorFormat::Json);
+        assert!(json.contains("\"line\":1,\"column\":7"), "{}", json);
+    }
+}
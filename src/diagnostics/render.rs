@@ -0,0 +1,207 @@
+use crate::diagnostics::diagnostic::{Diagnostic, Label};
+use std::fmt::Write;
+
+/// How diagnostics are written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorFormat {
+    #[default]
+    Human, // Source snippets with carets, for people
+    Json,  // One JSON object per line, for tools
+}
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Renders diagnostics against the source they were found in.
+pub struct Renderer<'a> {
+    name: &'a str,
+    source: &'a str,
+    color: bool,
+}
+
+// Where a byte offset falls in the source
+struct Position {
+    line: usize,   // 1-based
+    column: usize, // 1-based, in characters
+    line_start: usize,
+    line_end: usize, // Offset of the end of the line, before any '\n'
+    offset: usize,   // The offset itself, moved back into the source if needed
+}
+
+impl<'a> Renderer<'a> {
+    /// `name` is how the source is referred to in the output, e.g. its path.
+    pub fn new(name: &'a str, source: &'a str) -> Self {
+        Renderer { name, source, color: false }
+    }
+
+    /// Highlight the output with ANSI colors.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic, format: ErrorFormat) -> String {
+        match format {
+            ErrorFormat::Human => self.human(diagnostic),
+            ErrorFormat::Json => self.json(diagnostic),
+        }
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn position(&self, offset: usize) -> Position {
+        let mut offset = offset.min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line_start = self.source[..offset].rfind('\n').map_or(0, |n| n + 1);
+        let line_end = self.source[line_start..].find('\n').map_or(self.source.len(), |n| line_start + n);
+        Position {
+            line: self.source[..line_start].matches('\n').count() + 1,
+            column: self.source[line_start..offset].chars().count() + 1,
+            line_start,
+            line_end,
+            offset,
+        }
+    }
+
+    // The label the error is reported at
+    fn main_label<'d>(&self, diagnostic: &'d Diagnostic) -> Option<&'d Label> {
+        diagnostic.labels.iter().find(|l| l.primary).or(diagnostic.labels.first())
+    }
+
+    /// The diagnostic as text, quoting each labelled line of the source:
+    ///
+    /// ```text
+    /// error[E0001]: Expected Identifier
+    ///  --> main.lang:1:3
+    ///   |
+    /// 1 | ? : Number = 1
+    ///   |   ^ expected Identifier
+    /// ```
+    pub fn human(&self, diagnostic: &Diagnostic) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{}{}",
+            self.paint(RED, &format!("error[{}]", diagnostic.code)),
+            self.paint(BOLD, &format!(": {}", diagnostic.message))
+        );
+
+        let mut labels: Vec<(Position, &Label)> = diagnostic.labels.iter()
+            .map(|l| (self.position(l.span.start), l))
+            .collect();
+        labels.sort_by_key(|(p, _)| (p.line, p.column));
+        let width = labels.iter().map(|(p, _)| p.line.to_string().len()).max().unwrap_or(1);
+        let gutter = self.paint(BLUE, &format!("{:width$} |", ""));
+
+        if let Some(label) = self.main_label(diagnostic) {
+            let at = self.position(label.span.start);
+            let arrow = self.paint(BLUE, &format!("{:width$}-->", ""));
+            let _ = writeln!(out, "{} {}:{}:{}", arrow, self.name, at.line, at.column);
+            let _ = writeln!(out, "{}", gutter);
+        }
+
+        let mut previous: Option<usize> = None;
+        for (i, (at, label)) in labels.iter().enumerate() {
+            if previous != Some(at.line) {
+                if previous.is_some_and(|n| at.line > n + 1) {
+                    let _ = writeln!(out, "{}", self.paint(BLUE, "..."));
+                }
+                let text = expand_tabs(&self.source[at.line_start..at.line_end]);
+                let number = self.paint(BLUE, &format!("{:>width$} |", at.line));
+                let _ = writeln!(out, "{} {}", number, text.trim_end_matches('\r'));
+                previous = Some(at.line);
+            }
+            // Underline up to the end of the line for spans covering several
+            let start = at.offset;
+            let end = label.span.end.clamp(start, at.line_end);
+            let before = expand_tabs(&self.source[at.line_start..start]).chars().count();
+            let length = expand_tabs(&self.source[start..end]).chars().count().max(1);
+            let (marker, style) = if label.primary { ("^", RED) } else { ("-", BLUE) };
+            let underline = self.paint(style, &format!("{} {}", marker.repeat(length), label.message));
+            let _ = writeln!(out, "{} {:before$}{}", gutter, "", underline.trim_end());
+            if i + 1 == labels.len() && (!diagnostic.notes.is_empty() || diagnostic.help.is_some()) {
+                let _ = writeln!(out, "{}", gutter);
+            }
+        }
+
+        let pad = format!("{:width$}", "");
+        for note in &diagnostic.notes {
+            let _ = writeln!(out, "{} {} {} {}", pad, self.paint(BLUE, "="), self.paint(BOLD, "note:"), note);
+        }
+        if let Some(help) = &diagnostic.help {
+            let _ = writeln!(out, "{} {} {} {}", pad, self.paint(BLUE, "="), self.paint(BOLD, "help:"), help);
+        }
+        out
+    }
+
+    /// The diagnostic as a single line of JSON. Lines and columns are
+    /// 1-based, `start` and `end` are byte offsets into the source.
+    pub fn json(&self, diagnostic: &Diagnostic) -> String {
+        let labels: Vec<String> = diagnostic.labels.iter().map(|label| {
+            let at = self.position(label.span.start);
+            format!(
+                "{{\"primary\":{},\"message\":{},\"start\":{},\"end\":{},\"line\":{},\"column\":{}}}",
+                label.primary,
+                json_string(&label.message),
+                label.span.start,
+                label.span.end,
+                at.line,
+                at.column
+            )
+        }).collect();
+        let notes: Vec<String> = diagnostic.notes.iter().map(|n| json_string(n)).collect();
+        let (line, column) = match self.main_label(diagnostic) {
+            Some(label) => {
+                let at = self.position(label.span.start);
+                (at.line.to_string(), at.column.to_string())
+            }
+            None => ("null".to_string(), "null".to_string()),
+        };
+        format!(
+            "{{\"level\":\"error\",\"code\":{},\"message\":{},\"file\":{},\"line\":{},\"column\":{},\"labels\":[{}],\"notes\":[{}],\"help\":{}}}",
+            json_string(diagnostic.code),
+            json_string(&diagnostic.message),
+            json_string(self.name),
+            line,
+            column,
+            labels.join(","),
+            notes.join(","),
+            diagnostic.help.as_deref().map_or("null".to_string(), json_string)
+        )
+    }
+}
+
+// Tabs are shown as four spaces so carets line up whatever the terminal
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', "    ")
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
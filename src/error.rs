@@ -0,0 +1,63 @@
+use crate::checker::check_error::CheckError;
+use crate::interpreter::runtime_error::RuntimeError;
+use crate::lexer::lex_error::LexError;
+use crate::parser::parse_error::ParseError;
+
+/// Any error reported while reading, checking or running a program.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    Lex(LexError),
+    Parse(ParseError),
+    Check(CheckError),
+    Runtime(RuntimeError),
+}
+
+impl From<LexError> for Error {
+    fn from(err: LexError) -> Self {
+        Error::Lex(err)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Error::Parse(err)
+    }
+}
+
+impl From<CheckError> for Error {
+    fn from(err: CheckError) -> Self {
+        Error::Check(err)
+    }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(err: RuntimeError) -> Self {
+        Error::Runtime(err)
+    }
+}
+
+impl Error {
+    /// Stable code of the error: `E00xx` for syntax errors, `E01xx` for
+    /// lexical errors, `E02xx` for failed checks and `E03xx` at runtime.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Lex(err) => err.kind.code(),
+            Error::Parse(err) => err.code.as_str(),
+            Error::Check(err) => err.kind.code(),
+            Error::Runtime(_) => RuntimeError::CODE,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Lex(err) => err.fmt(f),
+            Error::Parse(err) => err.fmt(f),
+            Error::Check(err) => err.fmt(f),
+            Error::Runtime(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
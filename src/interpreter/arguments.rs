@@ -0,0 +1,197 @@
+use crate::parser::statement_ast::{Arity, Stmt};
+
+/// What a call needs to know about a parameter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamSpec {
+    pub name: String,
+    pub has_default: bool,
+    pub rest: bool, // `...name`, gathering the extra positional arguments
+}
+
+impl ParamSpec {
+    /// Specs of the `Stmt::Parameter`s of a declaration.
+    pub fn of(parameters: &[Stmt]) -> Vec<ParamSpec> {
+        parameters.iter().filter_map(|p| match p {
+            Stmt::Parameter { name, default, rest, .. } => Some(ParamSpec {
+                name: name.clone(),
+                has_default: default.is_some(),
+                rest: *rest,
+            }),
+            _ => None,
+        }).collect()
+    }
+}
+
+/// Where a parameter takes its value from in a call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+    Positional(usize), // Index among the positional arguments
+    Named(usize),      // Index among the named arguments
+    Rest(usize),       // A list of the positional arguments from this index on
+    Default,           // Not given, so the default value of the parameter
+}
+
+/// Why the arguments of a call do not fit the parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgumentError {
+    TooMany { expected: Arity, given: usize },
+    Unknown(String),
+    Duplicate(String),
+    NamedRest(String), // The rest parameter only gathers positional arguments
+    Missing { name: String, expected: Arity },
+}
+
+impl ArgumentError {
+    pub fn message(&self, function: &str) -> String {
+        match self {
+            ArgumentError::TooMany { expected, given } => {
+                let verb = if *given == 1 { "was" } else { "were" };
+                format!("{}() takes {} but {} {} given", function, expected, given, verb)
+            }
+            ArgumentError::Unknown(name) => format!("{}() has no parameter named `{}`", function, name),
+            ArgumentError::Duplicate(name) => format!("{}() got the argument `{}` more than once", function, name),
+            ArgumentError::NamedRest(name) => {
+                format!("`{}` gathers the extra arguments of {}() and cannot be given by name", name, function)
+            }
+            ArgumentError::Missing { name, expected } => {
+                format!("{}() is missing the argument `{}`; it takes {}", function, name, expected)
+            }
+        }
+    }
+}
+
+/// Match the arguments of a call to `parameters`, whose recorded arity is
+/// `arity`. Positional arguments fill the parameters in order, with any
+/// extra going to the rest parameter; named ones then fill the others by
+/// name. Used both by the checker, for calls to known functions, and at
+/// runtime.
+pub fn match_arguments(parameters: &[ParamSpec], arity: Arity, positional: usize, named: &[String]) -> Result<Vec<Slot>, Vec<ArgumentError>> {
+    let mut errors = Vec::new();
+    let fixed = parameters.iter().filter(|p| !p.rest).count();
+    let has_rest = fixed < parameters.len();
+    if positional > fixed && !has_rest {
+        errors.push(ArgumentError::TooMany { expected: arity, given: positional });
+    }
+    let mut slots: Vec<Option<Slot>> = parameters.iter().enumerate().map(|(i, p)| match p.rest {
+        true if positional > fixed => Some(Slot::Rest(fixed)),
+        true => None,
+        false => (i < positional).then_some(Slot::Positional(i)),
+    }).collect();
+    for (i, name) in named.iter().enumerate() {
+        match parameters.iter().position(|p| p.name == *name) {
+            None => errors.push(ArgumentError::Unknown(name.clone())),
+            Some(n) if parameters[n].rest => errors.push(ArgumentError::NamedRest(name.clone())),
+            Some(n) if slots[n].is_some() => errors.push(ArgumentError::Duplicate(name.clone())),
+            Some(n) => slots[n] = Some(Slot::Named(i)),
+        }
+    }
+    let slots = slots.into_iter().zip(parameters).map(|(slot, param)| match slot {
+        Some(slot) => slot,
+        // A rest parameter given nothing is an empty list
+        None if param.rest => Slot::Rest(positional),
+        None if param.has_default => Slot::Default,
+        None => {
+            errors.push(ArgumentError::Missing { name: param.name.clone(), expected: arity });
+            Slot::Default
+        }
+    }).collect();
+    if errors.is_empty() {
+        Ok(slots)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn param(name: &str) -> ParamSpec {
+        ParamSpec { name: name.to_string(), has_default: false, rest: false }
+    }
+
+    fn optional(name: &str) -> ParamSpec {
+        ParamSpec { has_default: true, ..param(name) }
+    }
+
+    fn rest(name: &str) -> ParamSpec {
+        ParamSpec { rest: true, ..param(name) }
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn positional_arguments_fill_parameters_in_order() {
+        let params = [param("a"), param("b")];
+        let slots = match_arguments(&params, Arity::Exact(2), 2, &[]);
+        assert_eq!(slots, Ok(vec![Slot::Positional(0), Slot::Positional(1)]));
+    }
+
+    #[test]
+    fn left_out_optional_parameters_take_their_default() {
+        let params = [param("a"), optional("b")];
+        let slots = match_arguments(&params, Arity::Range(1, 2), 1, &[]);
+        assert_eq!(slots, Ok(vec![Slot::Positional(0), Slot::Default]));
+    }
+
+    #[test]
+    fn named_arguments_fill_the_remaining_parameters() {
+        let params = [param("a"), optional("b"), param("c")];
+        let slots = match_arguments(&params, Arity::Range(2, 3), 1, &names(&["c", "b"]));
+        assert_eq!(slots, Ok(vec![Slot::Positional(0), Slot::Named(1), Slot::Named(0)]));
+    }
+
+    #[test]
+    fn extra_positional_arguments_go_to_the_rest_parameter() {
+        let params = [param("a"), rest("r")];
+        let slots = match_arguments(&params, Arity::AtLeast(1), 3, &[]);
+        assert_eq!(slots, Ok(vec![Slot::Positional(0), Slot::Rest(1)]));
+    }
+
+    #[test]
+    fn an_empty_rest_parameter_starts_after_the_arguments() {
+        let params = [param("a"), rest("r")];
+        let slots = match_arguments(&params, Arity::AtLeast(1), 1, &[]);
+        assert_eq!(slots, Ok(vec![Slot::Positional(0), Slot::Rest(1)]));
+    }
+
+    #[test]
+    fn too_many_arguments() {
+        let params = [param("a")];
+        let errors = match_arguments(&params, Arity::Exact(1), 2, &[]);
+        assert_eq!(errors, Err(vec![ArgumentError::TooMany { expected: Arity::Exact(1), given: 2 }]));
+    }
+
+    #[test]
+    fn missing_argument() {
+        let params = [param("a"), param("b")];
+        let errors = match_arguments(&params, Arity::Exact(2), 1, &[]);
+        assert_eq!(errors, Err(vec![ArgumentError::Missing { name: "b".to_string(), expected: Arity::Exact(2) }]));
+    }
+
+    #[test]
+    fn unknown_and_duplicate_names() {
+        let params = [param("a")];
+        let errors = match_arguments(&params, Arity::Exact(1), 1, &names(&["a", "z"]));
+        assert_eq!(errors, Err(vec![
+            ArgumentError::Duplicate("a".to_string()),
+            ArgumentError::Unknown("z".to_string()),
+        ]));
+    }
+
+    #[test]
+    fn the_rest_parameter_cannot_be_named() {
+        let params = [param("a"), rest("r")];
+        let errors = match_arguments(&params, Arity::AtLeast(1), 1, &names(&["r"]));
+        assert_eq!(errors, Err(vec![ArgumentError::NamedRest("r".to_string())]));
+    }
+
+    #[test]
+    fn messages_name_the_function() {
+        let err = ArgumentError::TooMany { expected: Arity::Range(1, 2), given: 3 };
+        assert_eq!(err.message("f"), "f() takes 1 to 2 arguments but 3 were given");
+        assert_eq!(ArgumentError::NamedRest("r".to_string()).message("g"), "`r` gathers the extra arguments of g() and cannot be given by name");
+    }
+}
@@ -0,0 +1,35 @@
+use crate::interpreter::value::{BuiltinFn, Value};
+
+/// Functions available everywhere without being declared.
+pub static BUILTINS: &[(&str, BuiltinFn)] = &[
+    ("print", print),
+    ("len", len),
+    ("str", str),
+];
+
+pub fn is_builtin(name: &str) -> bool {
+    BUILTINS.iter().any(|(n, _)| *n == name)
+}
+
+fn print(args: Vec<Value>) -> Result<Value, String> {
+    let parts: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+    println!("{}", parts.join(" "));
+    Ok(Value::Null)
+}
+
+fn len(args: Vec<Value>) -> Result<Value, String> {
+    match args.as_slice() {
+        [Value::String(s)] => Ok(Value::Number(s.chars().count() as f64)),
+        [Value::List(items)] => Ok(Value::Number(items.borrow().len() as f64)),
+        [Value::Map(entries)] => Ok(Value::Number(entries.borrow().len() as f64)),
+        [other] => Err(format!("len() does not accept a {}", other.type_name())),
+        _ => Err(format!("len() takes 1 argument but {} were given", args.len())),
+    }
+}
+
+fn str(args: Vec<Value>) -> Result<Value, String> {
+    match args.as_slice() {
+        [value] => Ok(Value::String(value.to_string())),
+        _ => Err(format!("str() takes 1 argument but {} were given", args.len())),
+    }
+}
@@ -0,0 +1,53 @@
+use crate::interpreter::value::Value;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub value: Value,
+    pub constant: bool,
+}
+
+/// One lexical scope. Scopes are shared so that functions can keep the
+/// scope they were declared in alive.
+#[derive(Debug, Default)]
+pub struct Scope {
+    pub vars: HashMap<String, Binding>,
+    pub parent: Option<Env>,
+}
+
+pub type Env = Rc<RefCell<Scope>>;
+
+pub fn new_env(parent: Option<Env>) -> Env {
+    Rc::new(RefCell::new(Scope { vars: HashMap::new(), parent }))
+}
+
+pub fn define(env: &Env, name: &str, value: Value, constant: bool) {
+    env.borrow_mut().vars.insert(name.to_string(), Binding { value, constant });
+}
+
+pub fn lookup(env: &Env, name: &str) -> Option<Value> {
+    let scope = env.borrow();
+    match scope.vars.get(name) {
+        Some(binding) => Some(binding.value.clone()),
+        None => scope.parent.as_ref().and_then(|p| lookup(p, name)),
+    }
+}
+
+/// Update an existing variable. Fails with a message when it is not
+/// declared or is a constant.
+pub fn assign(env: &Env, name: &str, value: Value) -> Result<(), String> {
+    let mut scope = env.borrow_mut();
+    match scope.vars.get_mut(name) {
+        Some(binding) if binding.constant => Err(format!("Cannot assign to the constant `{}`", name)),
+        Some(binding) => {
+            binding.value = value;
+            Ok(())
+        }
+        None => match &scope.parent {
+            Some(parent) => assign(parent, name, value),
+            None => Err(format!("Undefined variable `{}`", name)),
+        },
+    }
+}
@@ -0,0 +1,635 @@
+use crate::interpreter::arguments::{match_arguments, ParamSpec, Slot};
+use crate::interpreter::builtins::BUILTINS;
+use crate::interpreter::environment::*;
+use crate::interpreter::iteration::iterate;
+use crate::interpreter::map::OrderedMap;
+use crate::interpreter::runtime_error::*;
+use crate::interpreter::value::*;
+use crate::parser::expression_ast::{Expr, Token};
+use crate::parser::statement_ast::{Arity, Stmt};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// What a loop does once its body has run
+enum Flow {
+    Next,
+    Exit(Value),
+}
+
+// What an assignment writes to, with its object and key already evaluated
+enum Place {
+    Variable(String),
+    Element(Value, Value), // `object[key]`, or `object.key` with the name as a string
+}
+
+/// How deeply evaluation may nest, counting both nested expressions and
+/// function calls. Deeper programs fail with a runtime error rather than
+/// overflowing the stack, provided it holds `crate::STACK_SIZE` bytes.
+pub const MAX_DEPTH: usize = 10_000;
+
+/// Tree-walking evaluator for a parsed program.
+pub struct Interpreter {
+    pub globals: Env,
+    depth: usize, // Nesting of the expression being evaluated
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        let globals = new_env(None);
+        for (name, func) in BUILTINS {
+            define(&globals, name, Value::Builtin(name, *func), true);
+        }
+        Interpreter { globals, depth: 0 }
+    }
+
+    /// Run a `Stmt::Program` and return the value of its last statement.
+    pub fn run(&mut self, program: &Stmt) -> Result<Value, RuntimeError> {
+        let globals = self.globals.clone();
+        match self.exec(program, &globals) {
+            Ok(value) => Ok(value),
+            // A top level `return` ends the program
+            Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Error(err)) => Err(err),
+            // Rejected by the checker, but the program may not have been checked
+            Err(Unwind::Break { .. } | Unwind::Continue { .. }) => {
+                Err(RuntimeError::new("`break` and `continue` can only be used in a loop"))
+            }
+        }
+    }
+
+    // Run one turn of a loop labelled `label`, stopping at the `break` and
+    // `continue` aimed at it; those aimed at an outer loop keep unwinding.
+    fn loop_body(&mut self, label: &Option<String>, body: &[Stmt], env: &Env) -> EvalResult<Flow> {
+        let targets = |target: &Option<String>| target.is_none() || target == label;
+        match self.exec_block(body, &new_env(Some(env.clone()))) {
+            Ok(_) => Ok(Flow::Next),
+            Err(Unwind::Break { label, value }) if targets(&label) => Ok(Flow::Exit(value)),
+            Err(Unwind::Continue { label }) if targets(&label) => Ok(Flow::Next),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn exec_block(&mut self, body: &[Stmt], env: &Env) -> EvalResult<Value> {
+        // Functions can be called before the line that declares them
+        for stmt in body {
+            if let Stmt::FunctionDecl { .. } = stmt {
+                self.exec(stmt, env)?;
+            }
+        }
+        let mut last = Value::Null;
+        for stmt in body {
+            if let Stmt::FunctionDecl { .. } = stmt {
+                continue;
+            }
+            last = self.exec(stmt, env)?;
+        }
+        Ok(last)
+    }
+
+    pub fn exec(&mut self, stmt: &Stmt, env: &Env) -> EvalResult<Value> {
+        match stmt {
+            Stmt::Program { body } => self.exec_block(body, env),
+            Stmt::Block { body } => self.exec_block(body, &new_env(Some(env.clone()))),
+            Stmt::Expression { expression } => self.eval(expression, env),
+            Stmt::VariableDecl { name, value, constant, span, .. } => {
+                let value = locate(self.eval(value, env), *span)?;
+                define(env, name, value, *constant);
+                Ok(Value::Null)
+            }
+            Stmt::FunctionDecl { name, parameters, body, arity, .. } => {
+                let function = Function {
+                    name: name.clone(),
+                    parameters: parameter_list(parameters),
+                    arity: *arity,
+                    body: body.clone().unwrap_or_default(),
+                    closure: env.clone(),
+                    implicit_return: false,
+                };
+                define(env, name, Value::Function(Rc::new(function)), true);
+                Ok(Value::Null)
+            }
+            Stmt::Parameter { .. } => Ok(Value::Null),
+            Stmt::Error { .. } => runtime_error("Cannot run code that failed to parse"),
+        }
+    }
+
+    pub fn eval(&mut self, expr: &Expr, env: &Env) -> EvalResult<Value> {
+        if self.depth >= MAX_DEPTH {
+            return runtime_error(format!("Recursion limit exceeded: evaluation nested more than {} levels deep", MAX_DEPTH));
+        }
+        self.depth += 1;
+        let result = self.eval_expr(expr, env);
+        self.depth -= 1;
+        result
+    }
+
+    fn eval_expr(&mut self, expr: &Expr, env: &Env) -> EvalResult<Value> {
+        match expr {
+            Expr::Number { value } => Ok(Value::Number(*value)),
+            Expr::String { value, .. } => Ok(Value::String(value.clone())),
+            Expr::Boolean { value } => Ok(Value::Boolean(*value)),
+            Expr::Null => Ok(Value::Null),
+            Expr::Identifier { value, span } => match lookup(env, value) {
+                Some(n) => Ok(n),
+                None => locate(runtime_error(format!("Undefined variable `{}`", value)), *span),
+            },
+            Expr::TemplateString { parts } => {
+                let mut out = String::new();
+                for part in parts {
+                    out.push_str(&self.eval(part, env)?.to_string());
+                }
+                Ok(Value::String(out))
+            }
+            Expr::List { value, .. } => {
+                let mut items = Vec::with_capacity(value.len());
+                for item in value {
+                    items.push(self.eval(item, env)?);
+                }
+                Ok(Value::List(Rc::new(RefCell::new(items))))
+            }
+            Expr::Map { entries } => {
+                let mut map = OrderedMap::with_capacity(entries.len());
+                for (key, value) in entries {
+                    let key = self.eval(key, env)?;
+                    let value = self.eval(value, env)?;
+                    // A repeated key keeps its first position and its last value
+                    map.insert(key, value);
+                }
+                Ok(Value::Map(Rc::new(RefCell::new(map))))
+            }
+            Expr::MembreExpr { member, property } => {
+                let object = self.eval(member, env)?;
+                match property.as_ref() {
+                    Expr::Identifier { value: name, span } => locate(index(&object, &Value::String(name.clone())), *span),
+                    _ => runtime_error("Invalid property"),
+                }
+            }
+            Expr::ComputedExpr { member, property } => {
+                let object = self.eval(member, env)?;
+                let key = self.eval(property, env)?;
+                locate_at(member, index(&object, &key))
+            }
+            Expr::TernaryExpr { condition, true_value, false_value } => {
+                if self.eval(condition, env)?.is_truthy() {
+                    self.eval(true_value, env)
+                } else {
+                    self.eval(false_value, env)
+                }
+            }
+            Expr::UnaryExpr { operator, right } => {
+                let right = self.eval(right, env)?;
+                eval_unary(operator, right)
+            }
+            Expr::BinaryExpr { left, operator, right } => {
+                // `and` / `or` only evaluate their right side when needed
+                match operator.kind.as_str() {
+                    "AmperAmper" => {
+                        let left = self.eval(left, env)?;
+                        if !left.is_truthy() {
+                            return Ok(left);
+                        }
+                        self.eval(right, env)
+                    }
+                    "VerbarVerbar" => {
+                        let left = self.eval(left, env)?;
+                        if left.is_truthy() {
+                            return Ok(left);
+                        }
+                        self.eval(right, env)
+                    }
+                    _ => {
+                        let left = self.eval(left, env)?;
+                        let right = self.eval(right, env)?;
+                        eval_binary(operator, left, right)
+                    }
+                }
+            }
+            Expr::AssignmentExpr { assigne, value } => {
+                let place = self.place(assigne, env)?;
+                let value = self.eval(value, env)?;
+                locate_at(assigne, store(&place, value.clone(), env))?;
+                Ok(value)
+            }
+            Expr::CompoundAssignmentExpr { assigne, operator, value } => {
+                let place = self.place(assigne, env)?;
+                let current = match &place {
+                    Place::Variable(name) => match lookup(env, name) {
+                        Some(n) => n,
+                        None => return runtime_error(format!("Undefined variable `{}`", name)),
+                    },
+                    Place::Element(object, key) => index(object, key)?,
+                };
+                let right = self.eval(value, env)?;
+                let value = locate_at(assigne, eval_binary(operator, current, right))?;
+                locate_at(assigne, store(&place, value.clone(), env))?;
+                Ok(value)
+            }
+            Expr::CallExpr { caller, args, span } | Expr::NCallExpr { caller, args, span } => {
+                locate(self.eval_call(caller, args, env), *span)
+            }
+            Expr::IfExpr { body, else_branch } => {
+                for (condition, branch) in body {
+                    if self.eval(condition, env)?.is_truthy() {
+                        return self.exec_block(branch, &new_env(Some(env.clone())));
+                    }
+                }
+                self.exec_block(else_branch, &new_env(Some(env.clone())))
+            }
+            Expr::ForExpr { var_names, sequence, body, label, span } => {
+                let sequence = self.eval(sequence, env)?;
+                let Some(steps) = iterate(&sequence) else {
+                    return locate(runtime_error(format!("A {} is not iterable", sequence.type_name())), *span);
+                };
+                for step in steps {
+                    let scope = new_env(Some(env.clone()));
+                    match var_names.as_slice() {
+                        [name] => define(&scope, name, step.item, false),
+                        [key, value, ..] => {
+                            define(&scope, key, step.key, false);
+                            define(&scope, value, step.value, false);
+                        }
+                        [] => (),
+                    }
+                    if let Flow::Exit(_) = self.loop_body(label, body, &scope)? {
+                        break;
+                    }
+                }
+                Ok(Value::Null)
+            }
+            Expr::WhileExpr { condition, body, label } => {
+                while self.eval(condition, env)?.is_truthy() {
+                    if let Flow::Exit(_) = self.loop_body(label, body, env)? {
+                        break;
+                    }
+                }
+                Ok(Value::Null)
+            }
+            Expr::LoopExpr { body, label } => loop {
+                if let Flow::Exit(value) = self.loop_body(label, body, env)? {
+                    break Ok(value);
+                }
+            },
+            Expr::BreakExpr { label, value, .. } => {
+                let value = match value {
+                    Some(value) => self.eval(value, env)?,
+                    None => Value::Null,
+                };
+                Err(Unwind::Break { label: label.clone(), value })
+            }
+            Expr::ContinueExpr { label, .. } => Err(Unwind::Continue { label: label.clone() }),
+            Expr::RangeExpr { start, end } => match (self.eval(start, env)?, self.eval(end, env)?) {
+                (Value::Number(start), Value::Number(end)) => Ok(Value::Range(start, end)),
+                (start, end) => runtime_error(format!(
+                    "A range needs numbers but got a {} and a {}",
+                    start.type_name(),
+                    end.type_name()
+                )),
+            },
+            Expr::Lambda { parameters, body, .. } => Ok(Value::Function(Rc::new(Function {
+                name: "lambda".to_string(),
+                parameters: parameter_list(parameters),
+                arity: Arity::of(parameters),
+                body: body.clone(),
+                closure: env.clone(),
+                implicit_return: true,
+            }))),
+            Expr::Return { value } => {
+                let value = self.eval(value, env)?;
+                Err(Unwind::Return(value))
+            }
+            Expr::Error { .. } => runtime_error("Cannot run code that failed to parse"),
+            other => runtime_error(format!("{:?} is not supported by the interpreter yet", crate::parser::cst::SyntaxKind::of_expr(other))),
+        }
+    }
+
+    // Evaluate the object and key of an assignment target, once
+    fn place(&mut self, target: &Expr, env: &Env) -> EvalResult<Place> {
+        match target {
+            Expr::Identifier { value, .. } => Ok(Place::Variable(value.clone())),
+            Expr::MembreExpr { member, property } => match property.as_ref() {
+                Expr::Identifier { value, .. } => Ok(Place::Element(self.eval(member, env)?, Value::String(value.clone()))),
+                _ => runtime_error("Invalid property"),
+            },
+            Expr::ComputedExpr { member, property } => {
+                let object = self.eval(member, env)?;
+                Ok(Place::Element(object, self.eval(property, env)?))
+            }
+            _ => runtime_error("Invalid assignment target"),
+        }
+    }
+
+    // Evaluate the callee and arguments of a call, then make it
+    fn eval_call(&mut self, caller: &Expr, args: &[Expr], env: &Env) -> EvalResult<Value> {
+        let callee = self.eval(caller, env)?;
+        let mut positional = Vec::new();
+        let mut named = Vec::new();
+        for arg in args {
+            match arg {
+                Expr::Property { name, value } => named.push((name.clone(), self.eval(value, env)?)),
+                Expr::SpreadExpr { value } => match self.eval(value, env)? {
+                    Value::List(items) => positional.extend(items.borrow().iter().cloned()),
+                    other => return runtime_error(format!("Only a List can be spread into arguments, not a {}", other.type_name())),
+                },
+                _ => positional.push(self.eval(arg, env)?),
+            }
+        }
+        self.call(callee, positional, named)
+    }
+
+    /// Call `callee` with positional `args` and `named` arguments.
+    pub fn call(&mut self, callee: Value, args: Vec<Value>, named: Vec<(String, Value)>) -> EvalResult<Value> {
+        match callee {
+            Value::Builtin(name, _) if !named.is_empty() => {
+                runtime_error(format!("{}() does not take named arguments", name))
+            }
+            Value::Builtin(_, func) => func(args).map_err(|e| Unwind::Error(RuntimeError::new(e))),
+            Value::Function(func) => {
+                let signature: Vec<ParamSpec> = func.parameters.iter()
+                    .map(|p| ParamSpec { name: p.name.clone(), has_default: p.default.is_some(), rest: p.rest })
+                    .collect();
+                let names: Vec<String> = named.iter().map(|(n, _)| n.clone()).collect();
+                let slots = match match_arguments(&signature, func.arity, args.len(), &names) {
+                    Ok(n) => n,
+                    Err(errors) => return runtime_error(errors[0].message(&func.name)),
+                };
+                let mut args: Vec<Option<Value>> = args.into_iter().map(Some).collect();
+                let mut named: Vec<Option<Value>> = named.into_iter().map(|(_, v)| Some(v)).collect();
+                let scope = new_env(Some(func.closure.clone()));
+                // In order, so a default can use the parameters before it
+                for (param, slot) in func.parameters.iter().zip(slots) {
+                    let value = match (slot, &param.default) {
+                        (Slot::Positional(i), _) => args[i].take(),
+                        (Slot::Named(i), _) => named[i].take(),
+                        (Slot::Rest(i), _) => {
+                            let rest = args.iter_mut().skip(i).filter_map(Option::take).collect();
+                            Some(Value::List(Rc::new(RefCell::new(rest))))
+                        }
+                        (Slot::Default, Some(default)) => Some(self.eval(default, &scope)?),
+                        (Slot::Default, None) => None,
+                    };
+                    define(&scope, &param.name, value.unwrap_or(Value::Null), false);
+                }
+                match self.exec_block(&func.body, &scope) {
+                    Ok(value) if func.implicit_return => Ok(value),
+                    Ok(_) => Ok(Value::Null),
+                    Err(Unwind::Return(value)) => Ok(value),
+                    Err(err) => Err(err),
+                }
+            }
+            other => runtime_error(format!("A {} is not callable", other.type_name())),
+        }
+    }
+}
+
+fn parameter_list(parameters: &[Stmt]) -> Vec<Parameter> {
+    parameters.iter().filter_map(|p| match p {
+        Stmt::Parameter { name, default, rest, .. } => Some(Parameter {
+            name: name.clone(),
+            default: default.as_deref().cloned(),
+            rest: *rest,
+        }),
+        _ => None,
+    }).collect()
+}
+
+// `object[key]`, also used for `object.key` with the name as a string
+fn index(object: &Value, key: &Value) -> EvalResult<Value> {
+    match (object, key) {
+        (Value::Map(entries), _) => match entries.borrow().get(key) {
+            Some(value) => Ok(value.clone()),
+            None => runtime_error(format!("The map has no key {}", key)),
+        },
+        (Value::List(items), Value::Number(n)) => {
+            let items = items.borrow();
+            match list_position(*n, items.len()) {
+                Some(i) => Ok(items[i].clone()),
+                None => runtime_error(format!("Index {} is out of range for a List of length {}", n, items.len())),
+            }
+        }
+        (Value::String(s), Value::Number(n)) => {
+            let count = s.chars().count();
+            match list_position(*n, count).and_then(|i| s.chars().nth(i)) {
+                Some(c) => Ok(Value::String(c.to_string())),
+                None => runtime_error(format!("Index {} is out of range for a String of length {}", n, count)),
+            }
+        }
+        (Value::List(_) | Value::String(_), key) => {
+            runtime_error(format!("A {} is indexed by a Number, not a {}", object.type_name(), key.type_name()))
+        }
+        _ => runtime_error(format!("A {} cannot be indexed", object.type_name())),
+    }
+}
+
+// Locate the errors of `result` where `expr` starts, if that is known
+fn locate_at<T>(expr: &Expr, result: EvalResult<T>) -> EvalResult<T> {
+    match expr.start_span() {
+        Some(span) => locate(result, span),
+        None => result,
+    }
+}
+
+// Write `value` to `place`. Assigning a missing key of a map adds it.
+fn store(place: &Place, value: Value, env: &Env) -> EvalResult<()> {
+    let (object, key) = match place {
+        Place::Variable(name) => return Ok(assign(env, name, value).map_err(RuntimeError::new)?),
+        Place::Element(object, key) => (object, key),
+    };
+    match (object, key) {
+        (Value::Map(entries), _) => {
+            // The key may hold the map itself, so it is looked up before
+            // the map is borrowed for writing
+            let position = entries.borrow().position(key);
+            match position {
+                Some(i) => entries.borrow_mut().set(i, value),
+                None => entries.borrow_mut().push(key.clone(), value),
+            }
+            Ok(())
+        }
+        (Value::List(items), Value::Number(n)) => {
+            let mut items = items.borrow_mut();
+            let len = items.len();
+            match list_position(*n, len) {
+                Some(i) => {
+                    items[i] = value;
+                    Ok(())
+                }
+                None => runtime_error(format!("Index {} is out of range for a List of length {}", n, len)),
+            }
+        }
+        (Value::List(_), key) => runtime_error(format!("A List is indexed by a Number, not a {}", key.type_name())),
+        (Value::String(_), _) => runtime_error("A String cannot be changed in place"),
+        _ => runtime_error(format!("A {} cannot be indexed", object.type_name())),
+    }
+}
+
+// Position of index `n` in a sequence of `len` items; negative indexes
+// count from the end
+fn list_position(n: f64, len: usize) -> Option<usize> {
+    if n.fract() != 0.0 {
+        return None;
+    }
+    let i = if n < 0.0 { len as f64 + n } else { n };
+    (i >= 0.0 && i < len as f64).then_some(i as usize)
+}
+
+fn eval_unary(operator: &Token, right: Value) -> EvalResult<Value> {
+    match (operator.kind.as_str(), right) {
+        ("Minus", Value::Number(n)) => Ok(Value::Number(-n)),
+        ("Plus", Value::Number(n)) => Ok(Value::Number(n)),
+        ("ExclamationMark", value) => Ok(Value::Boolean(!value.is_truthy())),
+        (_, value) => runtime_error(format!("Cannot apply `{}` to a {}", operator.value, value.type_name())),
+    }
+}
+
+fn eval_binary(operator: &Token, left: Value, right: Value) -> EvalResult<Value> {
+    use Value::*;
+    let result = match (operator.kind.as_str(), &left, &right) {
+        ("EqualEqual", _, _) => Boolean(left == right),
+        ("ExclEqual", _, _) => Boolean(left != right),
+        ("Plus", Number(a), Number(b)) => Number(a + b),
+        ("Plus", String(a), b) => String(format!("{}{}", a, b)),
+        ("Plus", a, String(b)) => String(format!("{}{}", a, b)),
+        ("Minus", Number(a), Number(b)) => Number(a - b),
+        ("Star", Number(a), Number(b)) => Number(a * b),
+        ("Slash", Number(_), Number(b)) if *b == 0.0 => return runtime_error("Division by zero"),
+        ("Slash", Number(a), Number(b)) => Number(a / b),
+        ("Percent", Number(_), Number(b)) if *b == 0.0 => return runtime_error("Division by zero"),
+        ("Percent", Number(a), Number(b)) => Number(a % b),
+        // Only reachable through `&=`, `|=` and `^=`
+        ("Amper" | "Verbar" | "CircumFlex", Number(a), Number(b)) if a.fract() != 0.0 || b.fract() != 0.0 => {
+            return runtime_error(format!("`{}` needs whole numbers, not {} and {}", operator.value, a, b))
+        }
+        ("Amper" | "Verbar" | "CircumFlex", Number(a), Number(b)) => {
+            let (a, b) = (*a as i64, *b as i64);
+            Number(match operator.kind.as_str() {
+                "Amper" => a & b,
+                "Verbar" => a | b,
+                _ => a ^ b,
+            } as f64)
+        }
+        ("Inf", Number(a), Number(b)) => Boolean(a < b),
+        ("Sup", Number(a), Number(b)) => Boolean(a > b),
+        ("InfEqual", Number(a), Number(b)) => Boolean(a <= b),
+        ("SupEqual", Number(a), Number(b)) => Boolean(a >= b),
+        ("Inf", String(a), String(b)) => Boolean(a < b),
+        ("Sup", String(a), String(b)) => Boolean(a > b),
+        ("InfEqual", String(a), String(b)) => Boolean(a <= b),
+        ("SupEqual", String(a), String(b)) => Boolean(a >= b),
+        _ => {
+            return runtime_error(format!(
+                "Cannot apply `{}` to a {} and a {}",
+                operator.value,
+                left.type_name(),
+                right.type_name()
+            ))
+        }
+    };
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    // Value of the last statement of `source`, as `print` shows it
+    fn value(source: &str) -> String {
+        match crate::run(source) {
+            Ok(value) => value.to_string(),
+            Err(errors) => panic!("{}", errors[0]),
+        }
+    }
+
+    #[test]
+    fn an_if_gives_the_value_of_the_branch_taken() {
+        assert_eq!(value("?x: Number = 5\nif x < 3 { \"small\" } else if x < 10 { \"medium\" } else { \"large\" }\n"), "medium");
+        assert_eq!(value("if false { 1 }\n"), "null");
+        assert_eq!(value("?x: Number = if true { 1 } else { 2 }\nx\n"), "1");
+    }
+
+    #[test]
+    fn for_loops_bind_their_variables_each_turn() {
+        let source = "?out: String = \"\"\nfor k, v in {\"a\": 1, \"b\": 2} {\n    out = `${out}${k}=${v} `\n}\nfor i in 0..3 {\n    out = `${out}${i}`\n}\nout\n";
+        assert_eq!(value(source), "a=1 b=2 012");
+        assert_eq!(value("for c in \"ab\" { c }\n"), "null");
+    }
+
+    #[test]
+    fn only_sequences_can_be_looped_over() {
+        assert_eq!(runtime_error("for x in 5 { }\n").as_deref(), Some("error[E0301]: runtime error: A Number is not iterable"));
+    }
+
+    #[test]
+    fn loops_stop_and_skip_as_told() {
+        let source = "?n: Number = 0\n?out: String = \"\"\nwhile n < 6 {\n    n += 1\n    if n == 2 { continue }\n    if n == 5 { break }\n    out = `${out}${n}`\n}\nout\n";
+        assert_eq!(value(source), "134");
+    }
+
+    #[test]
+    fn break_gives_a_loop_its_value() {
+        assert_eq!(value("?n: Number = 0\nloop {\n    n += 1\n    if n == 3 { break n * 10 }\n}\n"), "30");
+        assert_eq!(value("loop { break }\n"), "null");
+    }
+
+    #[test]
+    fn labels_pick_the_loop_to_leave() {
+        let source = "?out: String = \"\"\n'outer: for i in 0..3 {\n    for j in 0..3 {\n        if j == 1 { continue 'outer }\n        if i == 2 { break 'outer }\n        out = `${out}${i}${j} `\n    }\n}\nout\n";
+        assert_eq!(value(source), "00 10 ");
+    }
+
+    #[test]
+    fn closures_share_the_scope_they_were_made_in() {
+        let counter = "?make: Any = lambda |start: Number| {\n    ?n: Number = start\n    lambda || {\n        n += 1\n        n\n    }\n}\n?next: Any = make(10)\nnext()\nnext()\n";
+        assert_eq!(value(counter), "12");
+        assert_eq!(value("?k: Number = 1\n?f: Any = lambda |x| x + k\nk = 5\nf(1)\n"), "6");
+        assert_eq!(value("?add: Any = lambda |x: Number, y| x + y\nadd(2, 3)\n"), "5");
+    }
+
+    #[test]
+    fn a_ternary_evaluates_only_the_branch_taken() {
+        assert_eq!(value("?a: Boolean = false\na ? 1 : true ? 2 : 3\n"), "2");
+        assert_eq!(value("true ? 1 : [][5]\n"), "1");
+        assert!(runtime_error("false ? 1 : [][5]\n").is_some());
+    }
+
+    #[test]
+    fn members_indexes_and_calls_chain() {
+        assert_eq!(value("?m: Any = {\"k\": [10, 20, 30]}\nm.k[-1]\n"), "30");
+        assert_eq!(value("\"hey\"[1]\n"), "e");
+        assert_eq!(value("?f: Any = lambda |n| {\"v\": n}\nf(4).v\n"), "4");
+        assert_eq!(value("?m: Any = {\"k\": [10, 20, 30]}\n-m.k[0]\n"), "-10");
+    }
+
+    #[test]
+    fn templates_format_their_parts() {
+        assert_eq!(value("?n: Number = 2\n`${n} + ${n} = ${n + n}, ${[1, \"a\"]} ${null}`\n"), "2 + 2 = 4, [1, \"a\"] null");
+    }
+
+    // Message of the runtime error raised by `source`, run with the stack
+    // the interpreter is meant for
+    fn runtime_error(source: &str) -> Option<String> {
+        let source = source.to_string();
+        std::thread::Builder::new()
+            .stack_size(crate::STACK_SIZE)
+            .spawn(move || crate::run(&source).err().map(|errors| errors[0].to_string()))
+            .unwrap()
+            .join()
+            .unwrap()
+    }
+
+    #[test]
+    fn deep_recursion_is_an_error() {
+        let source = "func f|n: Number|: Number {\n    return n < 1 ? 0 : 1 + f(n - 1)\n}\nf(100000)\n";
+        let err = runtime_error(source).expect("the recursion is too deep");
+        assert!(err.contains("Recursion limit exceeded"), "{}", err);
+    }
+
+    #[test]
+    fn recursion_below_the_limit_runs() {
+        let source = "func f|n: Number|: Number {\n    return n < 1 ? 0 : 1 + f(n - 1)\n}\nf(1000)\n";
+        assert_eq!(runtime_error(source), None);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::interpreter::map::OrderedMap;
+use crate::interpreter::value::Value;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// One turn of a `for` loop. `for x in seq` binds `item`, while
+/// `for k, v in seq` binds `key` and `value`.
+pub struct Step {
+    pub item: Value,
+    pub key: Value,   // The key of a map entry, or the position in the sequence
+    pub value: Value,
+}
+
+/// The iteration protocol: walks the values a `for` loop can go over.
+/// Lists and maps are read one step at a time, so the loop body may
+/// change them; a step past the current end finishes the loop.
+pub enum ValueIter {
+    List { items: Rc<RefCell<Vec<Value>>>, index: usize },
+    Map { entries: Rc<RefCell<OrderedMap>>, index: usize },
+    Range { next: f64, end: f64, index: usize },
+    Chars { chars: Vec<char>, index: usize },
+}
+
+/// Start iterating over `value`, or `None` if it is not iterable.
+pub fn iterate(value: &Value) -> Option<ValueIter> {
+    match value {
+        Value::List(items) => Some(ValueIter::List { items: items.clone(), index: 0 }),
+        Value::Map(entries) => Some(ValueIter::Map { entries: entries.clone(), index: 0 }),
+        Value::Range(start, end) => Some(ValueIter::Range { next: *start, end: *end, index: 0 }),
+        Value::String(s) => Some(ValueIter::Chars { chars: s.chars().collect(), index: 0 }),
+        _ => None,
+    }
+}
+
+impl Iterator for ValueIter {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Step> {
+        match self {
+            ValueIter::List { items, index } => {
+                let item = items.borrow().get(*index).cloned()?;
+                *index += 1;
+                Some(Step { key: Value::Number((*index - 1) as f64), value: item.clone(), item })
+            }
+            // A single variable goes over the keys of a map
+            ValueIter::Map { entries, index } => {
+                let (key, value) = entries.borrow().entry(*index).cloned()?;
+                *index += 1;
+                Some(Step { item: key.clone(), key, value })
+            }
+            ValueIter::Range { next, end, index } => {
+                if *next >= *end {
+                    return None;
+                }
+                let item = Value::Number(*next);
+                *next += 1.0;
+                *index += 1;
+                Some(Step { key: Value::Number((*index - 1) as f64), value: item.clone(), item })
+            }
+            ValueIter::Chars { chars, index } => {
+                let item = Value::String(chars.get(*index)?.to_string());
+                *index += 1;
+                Some(Step { key: Value::Number((*index - 1) as f64), value: item.clone(), item })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps(value: &Value) -> Vec<(String, String, String)> {
+        iterate(value).expect("the value is iterable")
+            .map(|s| (s.item.to_string(), s.key.to_string(), s.value.to_string()))
+            .collect()
+    }
+
+    fn step(item: &str, key: &str, value: &str) -> (String, String, String) {
+        (item.to_string(), key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn lists_give_their_items_and_positions() {
+        let list = Value::List(Rc::new(RefCell::new(vec![Value::Number(7.0), Value::Null])));
+        assert_eq!(steps(&list), vec![step("7", "0", "7"), step("null", "1", "null")]);
+    }
+
+    #[test]
+    fn maps_give_their_keys_in_order() {
+        let mut map = OrderedMap::default();
+        map.insert(Value::String("b".to_string()), Value::Number(1.0));
+        map.insert(Value::String("a".to_string()), Value::Number(2.0));
+        let map = Value::Map(Rc::new(RefCell::new(map)));
+        assert_eq!(steps(&map), vec![step("b", "b", "1"), step("a", "a", "2")]);
+    }
+
+    #[test]
+    fn ranges_exclude_their_end() {
+        assert_eq!(steps(&Value::Range(1.0, 3.0)), vec![step("1", "0", "1"), step("2", "1", "2")]);
+        assert_eq!(steps(&Value::Range(3.0, 1.0)), vec![]);
+    }
+
+    #[test]
+    fn strings_give_their_characters() {
+        assert_eq!(steps(&Value::String("hé".to_string())), vec![step("h", "0", "h"), step("é", "1", "é")]);
+    }
+
+    #[test]
+    fn other_values_are_not_iterable() {
+        assert!(iterate(&Value::Number(1.0)).is_none());
+        assert!(iterate(&Value::Null).is_none());
+    }
+
+    #[test]
+    fn a_list_grown_by_the_loop_is_walked_to_its_new_end() {
+        let items = Rc::new(RefCell::new(vec![Value::Number(1.0)]));
+        let mut iter = iterate(&Value::List(items.clone())).unwrap();
+        assert!(iter.next().is_some());
+        items.borrow_mut().push(Value::Number(2.0));
+        assert_eq!(iter.next().map(|s| s.item), Some(Value::Number(2.0)));
+        assert!(iter.next().is_none());
+    }
+}
@@ -0,0 +1,204 @@
+use crate::interpreter::value::Value;
+use std::collections::HashMap;
+
+/// The entries of a map value, in insertion order. Keys that can be
+/// hashed are found through an index; the others (lists, maps, functions
+/// and NaN) are compared one by one, as their contents may change.
+#[derive(Debug, Clone, Default)]
+pub struct OrderedMap {
+    entries: Vec<(Value, Value)>,
+    index: HashMap<Key, usize>, // Position of each hashable key in `entries`
+}
+
+// A map key as something hashable, equal exactly when the values are
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    Number(u64), // Bits of the number, with -0 made 0
+    String(String),
+    Boolean(bool),
+    Null,
+    Range(u64, u64),
+}
+
+impl Key {
+    fn of(value: &Value) -> Option<Key> {
+        let bits = |n: f64| (!n.is_nan()).then_some((n + 0.0).to_bits());
+        match value {
+            Value::Number(n) => bits(*n).map(Key::Number),
+            Value::String(s) => Some(Key::String(s.clone())),
+            Value::Boolean(b) => Some(Key::Boolean(*b)),
+            Value::Null => Some(Key::Null),
+            Value::Range(start, end) => Some(Key::Range(bits(*start)?, bits(*end)?)),
+            Value::List(_) | Value::Map(_) | Value::Function(_) | Value::Builtin(..) => None,
+        }
+    }
+}
+
+impl OrderedMap {
+    pub fn with_capacity(capacity: usize) -> Self {
+        OrderedMap { entries: Vec::with_capacity(capacity), index: HashMap::with_capacity(capacity) }
+    }
+
+    /// Position of `key` in insertion order.
+    pub fn position(&self, key: &Value) -> Option<usize> {
+        match Key::of(key) {
+            Some(hashed) => self.index.get(&hashed).copied(),
+            None => self.entries.iter().position(|(k, _)| k == key),
+        }
+    }
+
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        self.position(key).map(|i| &self.entries[i].1)
+    }
+
+    /// Set the value of `key`. A new key goes last; an existing one keeps
+    /// its position.
+    pub fn insert(&mut self, key: Value, value: Value) {
+        match self.position(&key) {
+            Some(i) => self.set(i, value),
+            None => self.push(key, value),
+        }
+    }
+
+    /// Set the value of the entry at position `i`.
+    pub fn set(&mut self, i: usize, value: Value) {
+        self.entries[i].1 = value;
+    }
+
+    /// Add an entry for `key`, which must not be in the map yet.
+    pub fn push(&mut self, key: Value, value: Value) {
+        if let Some(hashed) = Key::of(&key) {
+            self.index.insert(hashed, self.entries.len());
+        }
+        self.entries.push((key, value));
+    }
+
+    /// Remove `key` and give back its value. Later entries move up a place.
+    pub fn remove(&mut self, key: &Value) -> Option<Value> {
+        let i = self.position(key)?;
+        let (key, value) = self.entries.remove(i);
+        if let Some(hashed) = Key::of(&key) {
+            self.index.remove(&hashed);
+        }
+        for position in self.index.values_mut() {
+            if *position > i {
+                *position -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    /// The entry at position `i` in insertion order.
+    pub fn entry(&self, i: usize) -> Option<&(Value, Value)> {
+        self.entries.get(i)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(Value, Value)> {
+        self.entries.iter()
+    }
+}
+
+impl PartialEq for OrderedMap {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn num(n: f64) -> Value {
+        Value::Number(n)
+    }
+
+    fn text(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+
+    fn list(items: Vec<Value>) -> Value {
+        Value::List(Rc::new(RefCell::new(items)))
+    }
+
+    fn keys(map: &OrderedMap) -> Vec<Value> {
+        map.iter().map(|(k, _)| k.clone()).collect()
+    }
+
+    #[test]
+    fn replacing_a_key_keeps_its_position() {
+        let mut map = OrderedMap::default();
+        map.insert(text("a"), num(1.0));
+        map.insert(text("b"), num(2.0));
+        map.insert(text("a"), num(3.0));
+        assert_eq!(keys(&map), vec![text("a"), text("b")]);
+        assert_eq!(map.get(&text("a")), Some(&num(3.0)));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn a_removed_key_comes_back_last() {
+        let mut map = OrderedMap::default();
+        for (i, key) in ["a", "b", "c"].into_iter().enumerate() {
+            map.insert(text(key), num(i as f64));
+        }
+        assert_eq!(map.remove(&text("a")), Some(num(0.0)));
+        assert_eq!(map.remove(&text("a")), None);
+        assert_eq!(map.get(&text("c")), Some(&num(2.0)));
+        map.insert(text("a"), num(3.0));
+        assert_eq!(keys(&map), vec![text("b"), text("c"), text("a")]);
+        assert_eq!(map.get(&text("b")), Some(&num(1.0)));
+        assert_eq!(map.get(&text("a")), Some(&num(3.0)));
+    }
+
+    #[test]
+    fn equal_numbers_are_the_same_key() {
+        let mut map = OrderedMap::default();
+        map.insert(num(1.0), text("one"));
+        map.insert(num(0.0), text("zero"));
+        assert_eq!(map.get(&num("1.0".parse().unwrap())), Some(&text("one")));
+        assert_eq!(map.get(&num(-0.0)), Some(&text("zero")));
+        map.insert(num(-0.0), text("minus zero"));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&num(0.0)), Some(&text("minus zero")));
+    }
+
+    #[test]
+    fn nan_is_never_found() {
+        let mut map = OrderedMap::default();
+        map.insert(num(f64::NAN), num(1.0));
+        map.insert(num(f64::NAN), num(2.0));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&num(f64::NAN)), None);
+    }
+
+    #[test]
+    fn collections_are_found_by_their_contents() {
+        let key = list(vec![num(1.0)]);
+        let mut map = OrderedMap::default();
+        map.insert(key.clone(), text("list"));
+        assert_eq!(map.get(&list(vec![num(1.0)])), Some(&text("list")));
+        // The key changed in place is found by its new contents
+        if let Value::List(items) = &key {
+            items.borrow_mut().push(num(2.0));
+        }
+        assert_eq!(map.get(&list(vec![num(1.0)])), None);
+        assert_eq!(map.get(&list(vec![num(1.0), num(2.0)])), Some(&text("list")));
+
+        let inner = Value::Map(Rc::new(RefCell::new(OrderedMap::default())));
+        map.insert(inner, text("map"));
+        let empty = Value::Map(Rc::new(RefCell::new(OrderedMap::default())));
+        assert_eq!(map.get(&empty), Some(&text("map")));
+        assert_eq!(map.remove(&list(vec![num(1.0), num(2.0)])), Some(text("list")));
+        assert_eq!(keys(&map), vec![empty]);
+    }
+}
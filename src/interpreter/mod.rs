@@ -0,0 +1,9 @@
+pub mod value;
+pub mod environment;
+pub mod runtime_error;
+pub mod builtins;
+pub mod arguments;
+pub mod iteration;
+pub mod map;
+#[allow(clippy::module_inception)]
+pub mod interpreter;
@@ -0,0 +1,62 @@
+use crate::interpreter::value::Value;
+use crate::lexer::token::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub message: String,
+    pub span: Option<Span>, // The innermost name, call or declaration being run
+}
+
+impl RuntimeError {
+    /// Error code shared by every runtime error.
+    pub const CODE: &'static str = "E0301";
+
+    pub fn new(message: impl Into<String>) -> Self {
+        RuntimeError { message: message.into(), span: None }
+    }
+
+    /// Locate the error at `span`, unless a more precise place is known.
+    pub fn at(mut self, span: Span) -> Self {
+        self.span.get_or_insert(span);
+        self
+    }
+}
+
+impl std::fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error[{}]: runtime error: {}", Self::CODE, self.message)
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+/// Why evaluation stopped early: an error, or control flow leaving the
+/// current construct (e.g. `return` leaving a function).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Unwind {
+    Error(RuntimeError),
+    Return(Value),
+    Break { label: Option<String>, value: Value },
+    Continue { label: Option<String> },
+}
+
+impl From<RuntimeError> for Unwind {
+    fn from(err: RuntimeError) -> Self {
+        Unwind::Error(err)
+    }
+}
+
+pub type EvalResult<T> = Result<T, Unwind>;
+
+/// Shorthand for failing evaluation with `message`.
+pub fn runtime_error<T>(message: impl Into<String>) -> EvalResult<T> {
+    Err(Unwind::Error(RuntimeError::new(message)))
+}
+
+/// Locate an error coming out of `result` at `span`, see `RuntimeError::at`.
+pub fn locate<T>(result: EvalResult<T>, span: Span) -> EvalResult<T> {
+    result.map_err(|unwind| match unwind {
+        Unwind::Error(err) => Unwind::Error(err.at(span)),
+        other => other,
+    })
+}
@@ -0,0 +1,196 @@
+use crate::interpreter::environment::Env;
+use crate::interpreter::map::OrderedMap;
+use crate::parser::expression_ast::Expr;
+use crate::parser::statement_ast::{Arity, Stmt};
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Debug)]
+pub struct Parameter {
+    pub name: String,
+    pub default: Option<Expr>, // Evaluated on each call that leaves it out
+    pub rest: bool,
+}
+
+/// A user-defined function together with the scope it was declared in.
+#[derive(Debug)]
+pub struct Function {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    pub arity: Arity,
+    pub body: Vec<Stmt>,
+    pub closure: Env,
+    pub implicit_return: bool, // Lambdas give back the value of their last statement
+}
+
+pub type BuiltinFn = fn(args: Vec<Value>) -> Result<Value, String>;
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Number(f64),
+    String(String),
+    Boolean(bool),
+    Null,
+    List(Rc<RefCell<Vec<Value>>>),
+    Map(Rc<RefCell<OrderedMap>>),
+    Range(f64, f64), // `start..end`, end excluded
+    Function(Rc<Function>),
+    Builtin(&'static str, BuiltinFn),
+}
+
+impl Value {
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Boolean(b) => *b,
+            Value::Null => false,
+            _ => true,
+        }
+    }
+
+    /// Name of the value's type, for error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "Number",
+            Value::String(_) => "String",
+            Value::Boolean(_) => "Boolean",
+            Value::Null => "Null",
+            Value::List(_) => "List",
+            Value::Map(_) => "Map",
+            Value::Range(..) => "Range",
+            Value::Function(_) | Value::Builtin(..) => "Function",
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        equal(self, other, &mut Vec::new())
+    }
+}
+
+// Compare values that may contain themselves. `seen` holds the pairs of
+// collections being compared further up; meeting one again adds nothing
+// new, so the pair is taken as equal there.
+fn equal(a: &Value, b: &Value, seen: &mut Vec<(*const (), *const ())>) -> bool {
+    let pair = match (a, b) {
+        (Value::List(x), Value::List(y)) => Some((Rc::as_ptr(x) as *const (), Rc::as_ptr(y) as *const ())),
+        (Value::Map(x), Value::Map(y)) => Some((Rc::as_ptr(x) as *const (), Rc::as_ptr(y) as *const ())),
+        _ => None,
+    };
+    if let Some(pair) = pair {
+        if pair.0 == pair.1 || seen.contains(&pair) {
+            return true;
+        }
+        seen.push(pair);
+    }
+    let result = match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a == b,
+        (Value::String(a), Value::String(b)) => a == b,
+        (Value::Boolean(a), Value::Boolean(b)) => a == b,
+        (Value::Null, Value::Null) => true,
+        (Value::List(a), Value::List(b)) => {
+            let (a, b) = (a.borrow(), b.borrow());
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| equal(a, b, seen))
+        }
+        (Value::Map(a), Value::Map(b)) => {
+            let (a, b) = (a.borrow(), b.borrow());
+            a.len() == b.len()
+                && a.iter().zip(b.iter()).all(|((k1, v1), (k2, v2))| equal(k1, k2, seen) && equal(v1, v2, seen))
+        }
+        (Value::Range(a, b), Value::Range(c, d)) => a == c && b == d,
+        (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+        (Value::Builtin(a, _), Value::Builtin(b, _)) => a == b,
+        _ => false,
+    };
+    if pair.is_some() {
+        seen.pop();
+    }
+    result
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_value(f, self, &mut Vec::new())
+    }
+}
+
+// Write `value`, showing a collection found inside itself as `[...]` or
+// `{...}`. `path` holds the collections being written further up.
+fn write_value(f: &mut fmt::Formatter<'_>, value: &Value, path: &mut Vec<*const ()>) -> fmt::Result {
+    match value {
+        Value::Number(n) => write!(f, "{}", n),
+        Value::String(s) => write!(f, "{}", s),
+        Value::Boolean(b) => write!(f, "{}", b),
+        Value::Null => write!(f, "null"),
+        Value::List(items) if path.contains(&(Rc::as_ptr(items) as *const ())) => write!(f, "[...]"),
+        Value::Map(entries) if path.contains(&(Rc::as_ptr(entries) as *const ())) => write!(f, "{{...}}"),
+        Value::List(items) => {
+            path.push(Rc::as_ptr(items) as *const ());
+            write!(f, "[")?;
+            for (i, item) in items.borrow().iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_nested(f, item, path)?;
+            }
+            path.pop();
+            write!(f, "]")
+        }
+        Value::Map(entries) => {
+            path.push(Rc::as_ptr(entries) as *const ());
+            write!(f, "{{")?;
+            for (i, (key, value)) in entries.borrow().iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_nested(f, key, path)?;
+                write!(f, ": ")?;
+                write_nested(f, value, path)?;
+            }
+            path.pop();
+            write!(f, "}}")
+        }
+        Value::Range(start, end) => write!(f, "{}..{}", start, end),
+        Value::Function(func) => write!(f, "<func {}>", func.name),
+        Value::Builtin(name, _) => write!(f, "<builtin {}>", name),
+    }
+}
+
+// Strings inside collections are shown quoted
+fn write_nested(f: &mut fmt::Formatter<'_>, value: &Value, path: &mut Vec<*const ()>) -> fmt::Result {
+    match value {
+        Value::String(s) => write!(f, "{:?}", s),
+        _ => write_value(f, value, path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Output of `print` for the last line of `source`
+    fn printed(source: &str) -> String {
+        let value = crate::run(source).unwrap_or_else(|errors| panic!("{}", errors[0]));
+        value.to_string()
+    }
+
+    #[test]
+    fn a_list_inside_itself_is_shown_once() {
+        assert_eq!(printed("?a: Any = [0, 1]\na[0] = a\na\n"), "[[...], 1]");
+    }
+
+    #[test]
+    fn a_map_inside_itself_is_shown_once() {
+        assert_eq!(printed("?m: Any = {}\nm[\"self\"] = m\nm\n"), "{\"self\": {...}}");
+    }
+
+    #[test]
+    fn cyclic_values_can_be_compared() {
+        let source = "?a: Any = [0]\na[0] = a\n?b: Any = [0]\nb[0] = b\n[a == b, a == a, a == [a]]\n";
+        assert_eq!(printed(source), "[true, true, true]");
+    }
+
+    #[test]
+    fn a_map_can_be_its_own_key() {
+        assert_eq!(printed("?m: Any = {}\nm[m] = 1\nm[m] = 2\nlen(m)\n"), "1");
+    }
+}
@@ -0,0 +1,53 @@
+use crate::lexer::token::Span;
+
+/// What went wrong while reading a token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LexErrorKind {
+    UnknownCharacter,
+    UnterminatedString,
+    UnterminatedTemplate,
+    UnterminatedComment,
+    InvalidEscape,
+    InvalidNumber,
+}
+
+impl LexErrorKind {
+    /// Stable error code, shown in diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            LexErrorKind::UnknownCharacter => "E0101",
+            LexErrorKind::UnterminatedString => "E0102",
+            LexErrorKind::UnterminatedTemplate => "E0103",
+            LexErrorKind::UnterminatedComment => "E0104",
+            LexErrorKind::InvalidEscape => "E0105",
+            LexErrorKind::InvalidNumber => "E0106",
+        }
+    }
+}
+
+/// A lexical diagnostic. The lexer keeps going after reporting one, so a
+/// file can produce many.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub message: String,
+    pub span: Span,
+}
+
+impl LexError {
+    pub fn new(kind: LexErrorKind, message: impl Into<String>, span: Span) -> Self {
+        LexError {
+            kind,
+            message: message.into(),
+            span,
+        }
+    }
+}
+
+impl std::fmt::Display for LexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error[{}] {}:{}: {}", self.kind.code(), self.span.line, self.span.column, self.message)
+    }
+}
+
+impl std::error::Error for LexError {}
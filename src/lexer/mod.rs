@@ -1 +1,2 @@
-pub mod token;
\ No newline at end of file
+pub mod token;
+pub mod lex_error;
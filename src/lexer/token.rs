@@ -1,24 +1,35 @@
 
+use crate::lexer::lex_error::{LexError, LexErrorKind};
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
+use unicode_xid::UnicodeXID;
+#[cfg(feature = "nfc")]
+use unicode_normalization::UnicodeNormalization;
 
 #[derive(Debug, PartialEq, Clone, Copy, Eq, Hash)]
 pub enum TokenType {
     Number,		// 42
     Identifier,	// "x"
+    String,		// "abc" (value holds the decoded text)
+    Error,		// Text that could not be lexed, see `Lexer::errors`
 
 	QuestionMark,		// ?
 	ExclamationMark,	// !
 
 	BackQuote,			// `
-	DoubleQuote,		// "
+	TemplateText,		// text between ` and ${ (value holds the decoded text)
+	DollarBrace,		// ${
 	Quote,				// '
 	CircumFlex,			// ^
+	CircumFlexEqual,	// ^=
 	Amper,				// &
 	AmperAmper,			// &&
+	AmperEqual,			// &=
 	Verbar,				// |
 	VerbarVerbar,		// ||
+	VerbarEqual,		// |=
 	Percent,			// %
+	PercentEqual,		// %=
 	Tild,				// ~
     Plus,				// +
 	PlusEqual,			// +=
@@ -37,6 +48,8 @@ pub enum TokenType {
 	RBrace,				// }
 	At,					// @
 	Dot,				// .
+	DotDot,				// ..
+	DotDotDot,			// ...
 	Colon,				// :
 	ColonEqual,			// :=
 	SemiColon,			// ;
@@ -76,6 +89,35 @@ pub enum TokenType {
     InLoop,
     Break,
     Continue,
+    Switch,
+
+    // Module keyword
+    Module,
+
+    // Class & trait keyword
+    Class,
+    SelfRef,
+    Impl,
+
+    // Type keyword
+    Raw,
+    Extend,
+    TypeDecl,
+    True,
+    False,
+    Null,
+
+    // Data-type keyword
+    Interface,
+    Enum,
+
+    // Weak keyword, only reserved in their own context
+    Case,
+    Default,
+    Public,
+
+    // Future use
+    Extern,
 
     EOF,				// End Of File 
 }
@@ -116,26 +158,136 @@ pub static KEYWORDS: Lazy<HashMap<&'static str, TokenType>> = Lazy::new(|| {
     m.insert("in", TokenType::InLoop);
     m.insert("break", TokenType::Break);
     m.insert("continue", TokenType::Continue);
+    m.insert("switch", TokenType::Switch);
+
+    // Module keyword
+    m.insert("mod", TokenType::Module);
+
+    // Class & trait keyword
+    m.insert("class", TokenType::Class);
+    m.insert("self", TokenType::SelfRef);
+    m.insert("impl", TokenType::Impl);
+
+    // Type keyword
+    m.insert("raw", TokenType::Raw);
+    m.insert("extend", TokenType::Extend);
+    m.insert("type", TokenType::TypeDecl);
+    m.insert("true", TokenType::True);
+    m.insert("false", TokenType::False);
+    m.insert("null", TokenType::Null);
+
+    // Data-type keyword
+    m.insert("interface", TokenType::Interface);
+    m.insert("enum", TokenType::Enum);
+
+    // Future use
+    m.insert("extern", TokenType::Extern);
+
+    m
+});
+
+/// Keywords that only have a meaning in a given construct (`case` and
+/// `default` in a `switch`, `pub` on items); anywhere else the parser
+/// accepts them as plain identifiers.
+pub static WEAK_KEYWORDS: Lazy<HashMap<&'static str, TokenType>> = Lazy::new(|| {
+    let mut m = HashMap::new();
+
+    m.insert("case", TokenType::Case);
+    m.insert("default", TokenType::Default);
+    m.insert("pub", TokenType::Public);
 
     m
 });
 
+impl TokenType {
+    /// Whether the token is a weak keyword that may also be an identifier.
+    pub fn is_contextual(&self) -> bool {
+        matches!(self, TokenType::Case | TokenType::Default | TokenType::Public)
+    }
+}
+
+
 
+/// Location of a token in the source it was read from.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Span {
+    pub file_id: usize, // Index of the source file (0 for a single input)
+    pub start: usize,   // Byte offset of the first character
+    pub end: usize,     // Byte offset one past the last character
+    pub line: usize,    // 1-based line of `start`
+    pub column: usize,  // 1-based column (in characters) of `start`
+}
+
+impl Span {
+    /// Create a span covering `start..end` beginning at `line`:`column`.
+    pub fn new(file_id: usize, start: usize, end: usize, line: usize, column: usize) -> Self {
+        Self { file_id, start, end, line, column }
+    }
+
+    /// Smallest span covering both `self` and `other`.
+    pub fn to(&self, other: Span) -> Span {
+        if other.start < self.start {
+            return other.to(*self);
+        }
+        Span { end: self.end.max(other.end), ..*self }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TriviaKind {
+    Whitespace,		// Spaces and tabs (line breaks are NewLine tokens)
+    LineComment,	// // ...
+    BlockComment,	// /* ... */ (may nest)
+    DocComment,		// /// ...
+}
+
+/// Source text that carries no meaning for the parser, kept on the
+/// token that follows it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,	// Whitespace, or comment body without its delimiters
+    pub span: Span,
+}
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Token {
     pub kind: TokenType,
     pub value: String,  // Stores the raw text (e.g., "42", "+", "x")
-    // Optional: Add source location (e.g., for error reporting)
-    // pub span: Span,
+    pub span: Span,     // Where the token was found, for error reporting
+    pub number: Option<f64>, // Parsed value of a Number token
+    pub trivia: Vec<Trivia>, // Whitespace and comments found just before the token
 }
 
 impl Token {
     /// Create a new token with a kind and value.
+    /// The span is left empty; the lexer fills it in.
     pub fn new(kind: TokenType, value: impl Into<String>) -> Self {
         Self {
             kind,
             value: value.into(),
+            span: Span::default(),
+            number: None,
+            trivia: Vec::new(),
+        }
+    }
+
+    /// Create a new token with a kind, value and source location.
+    pub fn with_span(kind: TokenType, value: impl Into<String>, span: Span) -> Self {
+        Self {
+            kind,
+            value: value.into(),
+            span,
+            number: None,
+            trivia: Vec::new(),
         }
     }
 
@@ -148,74 +300,470 @@ impl Token {
     pub fn is_value(&self, kind: TokenType, value: &str) -> bool {
         self.kind == kind && self.value == value
     }
-}
 
+    /// Text of the `///` comments written just before the token, one per line.
+    pub fn doc_comment(&self) -> Option<String> {
+        let docs: Vec<&str> = self.trivia.iter()
+            .filter(|t| t.kind == TriviaKind::DocComment)
+            .map(|t| t.text.as_str())
+            .collect();
+        if docs.is_empty() {
+            return None;
+        }
+        Some(docs.join("\n"))
+    }
+}
 
-struct Lexer {
+/// Turns source text into tokens. Positions are byte offsets into the
+/// input so every step is constant time; iterate it to get the tokens,
+/// ending with a single EOF.
+pub struct Lexer {
     input: String,
-    position: usize,
+    position: usize,	// Byte offset of the current character
+    file_id: usize,
+    line: usize,
+    column: usize,
+    template_text: bool,	// Next token is literal text inside a `template`
+    templates: Vec<usize>,	// Open braces inside each enclosing ${ ... }
+    trivia: Vec<Trivia>,	// Whitespace and comments waiting for the next token
+    finished: bool,		// EOF has been returned by the iterator
+    errors: Vec<LexError>,	// Diagnostics reported so far
 }
 
 impl Lexer {
     pub fn new(input: String) -> Self {
-        Lexer { input, position: 0 }
+        Lexer::with_file(input, 0)
+    }
+
+    /// Create a lexer whose token spans refer to the source file `file_id`.
+    pub fn with_file(input: String, file_id: usize) -> Self {
+        Lexer {
+            input,
+            position: 0,
+            file_id,
+            line: 1,
+            column: 1,
+            template_text: false,
+            templates: Vec::new(),
+            trivia: Vec::new(),
+            finished: false,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Lexical errors found so far. Each one also produced an `Error`
+    /// token, or was recovered from inside a literal.
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
+
+    /// Take the lexical errors found so far, leaving none behind.
+    pub fn take_errors(&mut self) -> Vec<LexError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    // Span from `start` (at `line`:`column`) to the current character
+    fn span_from(&self, start: usize, line: usize, column: usize) -> Span {
+        Span::new(self.file_id, start, self.position, line, column)
+    }
+
+    // Record a diagnostic covering `start` to the current character
+    fn error(&mut self, kind: LexErrorKind, message: impl Into<String>, start: usize, line: usize, column: usize) {
+        let span = self.span_from(start, line, column);
+        self.errors.push(LexError::new(kind, message, span));
     }
 
     // Peek the current character without advancing
     fn peek(&self) -> Option<char> {
-        self.input.chars().nth(self.position)
+        self.input[self.position..].chars().next()
     }
 
     // Advance to the next character
     fn advance(&mut self) {
-        self.position += 1;
+        if let Some(c) = self.peek() {
+            self.position += c.len_utf8();
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
     }
 
-    // Skip whitespace
+    // Skip whitespace, except line breaks which are NewLine tokens
     fn skip_whitespace(&mut self) {
+        self.eat_while(|c| c.is_whitespace() && c != '\n');
+    }
+
+    // Skip whitespace and comments, keeping them as trivia
+    fn skip_trivia(&mut self) {
+        loop {
+            let (start, line, column) = (self.position, self.line, self.column);
+            let (kind, text) = match (self.peek(), self.peek_next()) {
+                (Some(c), _) if c.is_whitespace() && c != '\n' => {
+                    self.skip_whitespace();
+                    (TriviaKind::Whitespace, self.input[start..self.position].to_string())
+                }
+                (Some('/'), Some('/')) => self.read_line_comment(),
+                (Some('/'), Some('*')) => self.read_block_comment(),
+                _ => return,
+            };
+            let span = Span::new(self.file_id, start, self.position, line, column);
+            self.trivia.push(Trivia { kind, text, span });
+        }
+    }
+
+    // Read a // comment up to the end of the line; /// makes it a doc comment
+    fn read_line_comment(&mut self) -> (TriviaKind, String) {
+        self.advance();
+        self.advance();
+        // "////..." is a plain comment, as in Rust
+        let doc = self.peek() == Some('/') && self.peek_next() != Some('/');
+        if doc {
+            self.advance();
+        }
+        let start = self.position;
+        self.eat_while(|c| c != '\n');
+        let text = &self.input[start..self.position];
+        if doc {
+            (TriviaKind::DocComment, text.strip_prefix(' ').unwrap_or(text).to_string())
+        } else {
+            (TriviaKind::LineComment, text.to_string())
+        }
+    }
+
+    // Read a /* comment */, which may contain other block comments
+    fn read_block_comment(&mut self) -> (TriviaKind, String) {
+        let (comment_start, line, column) = (self.position, self.line, self.column);
+        self.advance();
+        self.advance();
+        let start = self.position;
+        let mut depth = 1;
+        loop {
+            match (self.peek(), self.peek_next()) {
+                (Some('/'), Some('*')) => {
+                    self.advance();
+                    self.advance();
+                    depth += 1;
+                }
+                (Some('*'), Some('/')) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        let text = self.input[start..self.position].to_string();
+                        self.advance();
+                        self.advance();
+                        return (TriviaKind::BlockComment, text);
+                    }
+                    self.advance();
+                    self.advance();
+                }
+                (Some(_), _) => self.advance(),
+                (None, _) => {
+                    self.error(LexErrorKind::UnterminatedComment, "Unterminated block comment", comment_start, line, column);
+                    return (TriviaKind::BlockComment, self.input[start..].to_string());
+                }
+            }
+        }
+    }
+
+    // Peek the character after the current one
+    fn peek_next(&self) -> Option<char> {
+        let mut chars = self.input[self.position..].chars();
+        chars.next();
+        chars.next()
+    }
+
+    // Consume characters while `f` holds
+    fn eat_while(&mut self, f: impl Fn(char) -> bool) {
         while let Some(c) = self.peek() {
-            if !c.is_whitespace() {
+            if !f(c) {
                 break;
             }
             self.advance();
         }
     }
 
-    // Read a number (e.g., "123" -> `Token::Number(123)`)
+    // Read a number (e.g., "123", "3.14", "1e9", "0xFF", "0b1010", "1_000")
     fn read_number(&mut self) -> Token {
         let start = self.position;
-        while let Some(c) = self.peek() {
-            if !c.is_ascii_digit() {
-                break;
+        let (line, column) = (self.line, self.column);
+        let value = self.scan_number();
+        // Swallow anything glued to the literal (e.g. "12abc") into the same token
+        if value.is_err() {
+            self.eat_while(|c| c.is_alphanumeric() || c == '_');
+        }
+        let text = &self.input[start..self.position];
+        match value {
+            Ok(value) => {
+                let mut token = Token::new(TokenType::Number, text);
+                token.number = Some(value);
+                token
+            }
+            Err(message) => {
+                let token = Token::new(TokenType::Error, text);
+                self.error(LexErrorKind::InvalidNumber, message, start, line, column);
+                token
             }
-            self.advance();
         }
-        let num_str = &self.input[start..self.position];
-        Token::new(TokenType::Number, num_str.parse::<String>().unwrap())
     }
 
-    // Read an identifier (e.g., "x" -> `Token::Identifier("x")`)
-    fn read_identifier(&mut self) -> Token {
+    // Consume a numeric literal and compute its value
+    fn scan_number(&mut self) -> Result<f64, String> {
         let start = self.position;
-        while let Some(c) = self.peek() {
-            if !c.is_alphabetic() {
-                break;
+        let radix = match (self.peek(), self.peek_next()) {
+            (Some('0'), Some('x' | 'X')) => 16,
+            (Some('0'), Some('o' | 'O')) => 8,
+            (Some('0'), Some('b' | 'B')) => 2,
+            _ => 10,
+        };
+
+        if radix == 10 {
+            self.eat_while(|c| c.is_ascii_digit() || c == '_');
+            if self.peek() == Some('.') && self.peek_next().is_some_and(|c| c.is_ascii_digit()) {
+                self.advance();
+                self.eat_while(|c| c.is_ascii_digit() || c == '_');
+            }
+            if let Some('e' | 'E') = self.peek() {
+                self.advance();
+                if let Some('+' | '-') = self.peek() {
+                    self.advance();
+                }
+                if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    return Err("Expected digits in the exponent of a number".to_string());
+                }
+                self.eat_while(|c| c.is_ascii_digit() || c == '_');
+            }
+            // A letter glued to the number (e.g. "12abc") is a malformed literal
+            if self.peek().is_some_and(|c| c.is_alphanumeric()) {
+                self.eat_while(|c| c.is_alphanumeric() || c == '_');
+                return Err(format!("Invalid number {}", &self.input[start..self.position]));
+            }
+            let text = &self.input[start..self.position];
+            if misplaced_underscore(text, 10) {
+                return Err(format!("Invalid number {}: '_' can only separate digits", text));
+            }
+            let value = text.replace('_', "").parse::<f64>().map_err(|_| format!("Invalid number {}", text))?;
+            if value.is_infinite() {
+                return Err(format!("The number {} is too large", text));
+            }
+            return Ok(value);
+        }
+
+        self.advance();
+        self.advance();
+        let digits_start = self.position;
+        self.eat_while(|c| c.is_alphanumeric() || c == '_');
+        let raw = &self.input[digits_start..self.position];
+        let digits = raw.replace('_', "");
+        let name = match radix {
+            16 => "hexadecimal",
+            8 => "octal",
+            _ => "binary",
+        };
+        if digits.is_empty() {
+            return Err(format!("Expected digits after {}", &self.input[start..digits_start]));
+        }
+        if let Some(c) = digits.chars().find(|c| !c.is_digit(radix)) {
+            return Err(format!("Invalid digit '{}' in {} literal", c, name));
+        }
+        if misplaced_underscore(raw, radix) {
+            return Err(format!("Invalid number {}: '_' can only separate digits", &self.input[start..self.position]));
+        }
+        // Exact up to 64 bits; beyond, the value is rounded like any float
+        let value = match u64::from_str_radix(&digits, radix) {
+            Ok(n) => n as f64,
+            Err(_) => digits.chars().fold(0.0, |n, c| n * radix as f64 + c.to_digit(radix).unwrap_or(0) as f64),
+        };
+        if value.is_infinite() {
+            return Err(format!("The {} literal {} is too large", name, &self.input[start..self.position]));
+        }
+        Ok(value)
+    }
+
+    // Read a string literal (e.g., "a\tb" -> `Token::String("a<tab>b")`)
+    fn read_string(&mut self) -> Token {
+        let (start, line, column) = (self.position, self.line, self.column);
+        self.advance(); // opening quote
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                Some('"') => {
+                    self.advance();
+                    break;
+                }
+                Some('\\') => {
+                    self.advance();
+                    value.extend(self.read_escape());
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.advance();
+                }
+                None => {
+                    self.error(LexErrorKind::UnterminatedString, "Unterminated string literal", start, line, column);
+                    return Token::new(TokenType::Error, &self.input[start..]);
+                }
             }
-            self.advance();
         }
+        Token::new(TokenType::String, value)
+    }
+
+    // Decode the escape sequence following a backslash. Invalid escapes are
+    // reported and dropped from the decoded text.
+    fn read_escape(&mut self) -> Option<char> {
+        // The backslash is a single byte, just before the current character
+        let (start, line, column) = (self.position - 1, self.line, self.column - 1);
+        let c = self.peek();
+        self.advance();
+        let decoded = match c {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('"') => '"',
+            Some('\'') => '\'',
+            Some('\\') => '\\',
+            Some('`') => '`',
+            Some('$') => '$',
+            Some('u') => {
+                if self.peek() != Some('{') {
+                    self.error(LexErrorKind::InvalidEscape, "Expected '{' after \\u", start, line, column);
+                    return None;
+                }
+                self.advance();
+                let mut hex = String::new();
+                while let Some(c) = self.peek() {
+                    if !c.is_ascii_hexdigit() {
+                        break;
+                    }
+                    hex.push(c);
+                    self.advance();
+                }
+                if self.peek() != Some('}') {
+                    self.error(LexErrorKind::InvalidEscape, "Unterminated unicode escape", start, line, column);
+                    return None;
+                }
+                self.advance();
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(n) => n,
+                    None => {
+                        self.error(LexErrorKind::InvalidEscape, format!("Invalid unicode escape \\u{{{}}}", hex), start, line, column);
+                        return None;
+                    }
+                }
+            }
+            Some(c) => {
+                self.error(LexErrorKind::InvalidEscape, format!("Unknown escape sequence \\{}", c.escape_debug()), start, line, column);
+                return None;
+            }
+            // The literal itself reports being unterminated
+            None => return None,
+        };
+        Some(decoded)
+    }
+
+    // Read the inside of a `template` up to the closing backquote or the next ${
+    fn read_template(&mut self) -> Token {
+        let (start, line, column) = (self.position, self.line, self.column);
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                Some('`') => {
+                    if !value.is_empty() {
+                        break;
+                    }
+                    self.advance();
+                    self.template_text = false;
+                    return Token::new(TokenType::BackQuote, "`");
+                }
+                Some('$') if self.peek_next() == Some('{') => {
+                    if !value.is_empty() {
+                        break;
+                    }
+                    self.advance();
+                    self.advance();
+                    self.template_text = false;
+                    self.templates.push(0);
+                    return Token::new(TokenType::DollarBrace, "${");
+                }
+                Some('\\') => {
+                    self.advance();
+                    value.extend(self.read_escape());
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.advance();
+                }
+                None => {
+                    self.error(LexErrorKind::UnterminatedTemplate, "Unterminated template string", start, line, column);
+                    self.template_text = false;
+                    self.templates.clear();
+                    return Token::new(TokenType::Error, &self.input[start..]);
+                }
+            }
+        }
+        Token::new(TokenType::TemplateText, value)
+    }
+
+    // Read an identifier (e.g., "user_id" -> `Token::Identifier("user_id")`)
+    // Identifiers start with `_` or an XID_Start character and continue
+    // with XID_Continue characters (letters, digits, `_`, ...).
+    fn read_identifier(&mut self) -> Token {
+        let start = self.position;
+        self.eat_while(|c| c.is_xid_continue());
         let ident = &self.input[start..self.position];
-		if KEYWORDS.get(ident).is_some(){
-			return Token::new(*KEYWORDS.get(ident).unwrap(), ident.parse::<String>().unwrap())
+        #[cfg(feature = "nfc")]
+        let ident: &str = &ident.nfc().collect::<String>();
+		if let Some(kind) = KEYWORDS.get(ident).or_else(|| WEAK_KEYWORDS.get(ident)) {
+			return Token::new(*kind, ident)
 		}
-        Token::new(TokenType::Identifier, ident.parse::<String>().unwrap())
+        Token::new(TokenType::Identifier, ident)
+    }
+
+    // Track braces so the } closing a ${ resumes the template text
+    fn open_brace(&mut self) {
+        if let Some(depth) = self.templates.last_mut() {
+            *depth += 1;
+        }
+    }
+
+    fn close_brace(&mut self) {
+        match self.templates.last_mut() {
+            Some(0) => {
+                self.templates.pop();
+                self.template_text = true;
+            }
+            Some(depth) => *depth -= 1,
+            None => (),
+        }
     }
 
     // Main lexing logic
     pub fn next_token(&mut self) -> Token {
-        self.skip_whitespace();
+        if !self.template_text {
+            self.skip_trivia();
+        }
+
+		let (start, line, column) = (self.position, self.line, self.column);
+		let mut token = self.scan_token();
+		token.span = Span::new(self.file_id, start, self.position, line, column);
+		token.trivia = std::mem::take(&mut self.trivia);
+		token
+    }
 
-		if let Some(c1) = self.peek() {
-            if let Some(c2) = self.input.chars().nth(self.position + 1) {
+    // Read the token starting at the current character
+    fn scan_token(&mut self) -> Token {
+		if self.template_text {
+			return self.read_template();
+		}
+
+		if self.input[self.position..].starts_with("...") {
+			self.advance(); self.advance(); self.advance();
+			return Token::new(TokenType::DotDotDot, "...");
+		}
+
+		if let (Some(c1), Some(c2)) = (self.peek(), self.peek_next()) {
                 match (c1, c2) {
 					('=', '=') => { self.advance(); self.advance(); return Token::new(TokenType::EqualEqual,  "==")},
 					('!', '=') => { self.advance(); self.advance(); return Token::new(TokenType::ExclEqual,  "!=")},
@@ -225,20 +773,24 @@ impl Lexer {
 					('+', '=') => { self.advance(); self.advance(); return Token::new(TokenType::PlusEqual,  "+=")},
 					('-', '=') => { self.advance(); self.advance(); return Token::new(TokenType::MinusEqual,  "-=")},
 					('*', '=') => { self.advance(); self.advance(); return Token::new(TokenType::StarEqual,  "*=")},
-					('/', '=') => { self.advance(); self.advance(); return Token::new(TokenType::EqualEqual,  "/=")},
+					('/', '=') => { self.advance(); self.advance(); return Token::new(TokenType::SlashEqual,  "/=")},
+					('%', '=') => { self.advance(); self.advance(); return Token::new(TokenType::PercentEqual,  "%=")},
+					('&', '=') => { self.advance(); self.advance(); return Token::new(TokenType::AmperEqual,  "&=")},
+					('|', '=') => { self.advance(); self.advance(); return Token::new(TokenType::VerbarEqual,  "|=")},
+					('^', '=') => { self.advance(); self.advance(); return Token::new(TokenType::CircumFlexEqual,  "^=")},
+					('.', '.') => { self.advance(); self.advance(); return Token::new(TokenType::DotDot,  "..")},
 					(':', '=') => { self.advance(); self.advance(); return Token::new(TokenType::ColonEqual,  ":=")},
 					('-', '>') => { self.advance(); self.advance(); return Token::new(TokenType::Arrow,  "->")},
 					('=', '>') => { self.advance(); self.advance(); return Token::new(TokenType::DoubleArrow,  "=>")},
                     _ => (), // No match, fall through to single-character tokens
                 }
-            }
         }
 
     match self.peek() {
     	Some('?') => { self.advance(); Token::new(TokenType::QuestionMark, "?") },
     	Some('!') => { self.advance(); Token::new(TokenType::ExclamationMark, "!") },
-    	Some('`') => { self.advance(); Token::new(TokenType::BackQuote, "`") },
-    	Some('"') => { self.advance(); Token::new(TokenType::DoubleQuote, "\"") },
+    	Some('`') => { self.advance(); self.template_text = true; Token::new(TokenType::BackQuote, "`") },
+    	Some('"') => self.read_string(),
     	Some('\'') => { self.advance(); Token::new(TokenType::Quote, "'") },
     	Some('^') => { self.advance(); Token::new(TokenType::CircumFlex, "^") },
     	Some('&') => { self.advance(); Token::new(TokenType::Amper, "&") },
@@ -254,30 +806,179 @@ impl Lexer {
     	Some(')') => { self.advance(); Token::new(TokenType::RParen, ")") },
     	Some('[') => { self.advance(); Token::new(TokenType::LBracket, "[") },
     	Some(']') => { self.advance(); Token::new(TokenType::RBracket, "]") },
-    	Some('{') => { self.advance(); Token::new(TokenType::LBrace, "{") },
-    	Some('}') => { self.advance(); Token::new(TokenType::RBrace, "}") },
+    	Some('{') => { self.advance(); self.open_brace(); Token::new(TokenType::LBrace, "{") },
+    	Some('}') => { self.advance(); self.close_brace(); Token::new(TokenType::RBrace, "}") },
     	Some('@') => { self.advance(); Token::new(TokenType::At, "@") },
+    	Some('.') => { self.advance(); Token::new(TokenType::Dot, ".") },
     	Some(':') => { self.advance(); Token::new(TokenType::Colon, ":") },
     	Some(';') => { self.advance(); Token::new(TokenType::SemiColon, ";") },
+    	Some(',') => { self.advance(); Token::new(TokenType::Comma, ",") },
     	Some('#') => { self.advance(); Token::new(TokenType::Hash, "#") },
     	Some('=') => { self.advance(); Token::new(TokenType::Equal, "=") },
     	Some('>') => { self.advance(); Token::new(TokenType::Sup, ">") },
     	Some('<') => { self.advance(); Token::new(TokenType::Inf, "<") },
     	Some('\n') => { self.advance(); Token::new(TokenType::NewLine, "\n") },
     	Some(c) if c.is_ascii_digit() => self.read_number(),
-    	Some(c) if c.is_alphabetic() => self.read_identifier(),
+    	Some(c) if c == '_' || c.is_xid_start() => self.read_identifier(),
     	None => Token::new(TokenType::EOF, ""),
-    _ => panic!("Unexpected character: {:?}", self.peek()),
+    	Some(c) => {
+    	    let (start, line, column) = (self.position, self.line, self.column);
+    	    self.advance();
+    	    self.error(LexErrorKind::UnknownCharacter, format!("Unexpected character {:?}", c), start, line, column);
+    	    Token::new(TokenType::Error, c)
+    	},
 }
 
     }
 
-	pub fn tokenize(&mut self, input: String) -> Vec<Token> {
-		let mut tokens: Vec<Token> = Vec::new();
-		while input.len() > self.position {
-			tokens.push(self.next_token());
-			self.advance();
-		};
-		tokens
+	/// Lex the remaining input, ending with an EOF token.
+	pub fn tokenize(&mut self) -> Vec<Token> {
+		self.by_ref().collect()
+	}
+}
+
+impl Iterator for Lexer {
+	type Item = Token;
+
+	fn next(&mut self) -> Option<Token> {
+		if self.finished {
+			return None;
+		}
+		let token = self.next_token();
+		self.finished = token.kind == TokenType::EOF;
+		Some(token)
 	}
 }
+
+// An '_' in a number must sit between two digits: `1_000`, not `1_`, `1__0` or `1_.5`
+fn misplaced_underscore(text: &str, radix: u32) -> bool {
+	let chars: Vec<char> = text.chars().collect();
+	chars.iter().enumerate().any(|(i, &c)| {
+		c == '_' && !(i > 0 && chars[i - 1].is_digit(radix) && chars.get(i + 1).is_some_and(|c| c.is_digit(radix)))
+	})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Kinds and values of the tokens of `source`, without the final EOF,
+    // along with the kinds of the errors found
+    fn lex(source: &str) -> (Vec<(TokenType, String)>, Vec<LexErrorKind>) {
+        let mut lexer = Lexer::new(source.to_string());
+        let mut tokens: Vec<_> = lexer.tokenize().into_iter().map(|t| (t.kind, t.value)).collect();
+        tokens.pop();
+        (tokens, lexer.take_errors().into_iter().map(|e| e.kind).collect())
+    }
+
+    fn string(value: &str) -> (TokenType, String) {
+        (TokenType::String, value.to_string())
+    }
+
+    // Value of `source` read as a single number literal
+    fn number(source: &str) -> Result<f64, String> {
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize();
+        match lexer.take_errors().first() {
+            Some(err) => Err(err.message.clone()),
+            None => {
+                assert_eq!(tokens[0].kind, TokenType::Number, "{} is not a number", source);
+                Ok(tokens[0].number.expect("a number token carries its value"))
+            }
+        }
+    }
+
+    #[test]
+    fn decimal_literals() {
+        assert_eq!(number("42"), Ok(42.0));
+        assert_eq!(number("2.75"), Ok(2.75));
+        assert_eq!(number("1e9"), Ok(1e9));
+        assert_eq!(number("2.5E-3"), Ok(2.5e-3));
+        assert_eq!(number("1_000_000"), Ok(1_000_000.0));
+    }
+
+    #[test]
+    fn radix_literals() {
+        assert_eq!(number("0xFF"), Ok(255.0));
+        assert_eq!(number("0o17"), Ok(15.0));
+        assert_eq!(number("0b1010"), Ok(10.0));
+        assert_eq!(number("0b1111_0000"), Ok(240.0));
+    }
+
+    #[test]
+    fn radix_literals_above_u64_are_rounded() {
+        assert_eq!(number("0x1_0000_0000_0000_0000"), Ok(18446744073709551616.0));
+    }
+
+    #[test]
+    fn literals_too_large_for_a_number() {
+        assert_eq!(number("1e999"), Err("The number 1e999 is too large".to_string()));
+        assert_eq!(number("1_0e400"), Err("The number 1_0e400 is too large".to_string()));
+        assert!(number(&format!("0x{}", "F".repeat(300))).is_err_and(|err| err.ends_with("is too large")));
+        assert_eq!(number("1e308"), Ok(1e308));
+        assert_eq!(number("1e-999"), Ok(0.0));
+    }
+
+    #[test]
+    fn invalid_digits() {
+        assert_eq!(number("0b2"), Err("Invalid digit '2' in binary literal".to_string()));
+        assert_eq!(number("0xG"), Err("Invalid digit 'G' in hexadecimal literal".to_string()));
+        assert_eq!(number("0x"), Err("Expected digits after 0x".to_string()));
+        assert!(number("12abc").is_err());
+        assert!(number("1e").is_err());
+    }
+
+    #[test]
+    fn underscores_only_separate_digits() {
+        for source in ["1_000_", "1__0", "1_.5", "0x_ff", "0b1_"] {
+            assert!(number(source).is_err_and(|err| err.contains("'_' can only separate digits")), "{} was accepted", source);
+        }
+    }
+
+    #[test]
+    fn string_escapes_are_decoded() {
+        assert_eq!(lex(r#""a\tb\n\"q\" \\ \0""#), (vec![string("a\tb\n\"q\" \\ \0")], vec![]));
+        assert_eq!(lex(r#""\u{41}\u{1F600}""#), (vec![string("A\u{1F600}")], vec![]));
+        assert_eq!(lex(r#""it's \' \` \$""#), (vec![string("it's ' ` $")], vec![]));
+    }
+
+    #[test]
+    fn invalid_escapes_are_reported() {
+        let (tokens, errors) = lex(r#""bad \q" 1"#);
+        assert_eq!(errors, vec![LexErrorKind::InvalidEscape]);
+        assert_eq!(tokens[1], (TokenType::Number, "1".to_string()));
+        assert_eq!(lex(r#""\u{110000}""#).1, vec![LexErrorKind::InvalidEscape]);
+        assert_eq!(lex(r#""\u{41""#).1, vec![LexErrorKind::InvalidEscape]);
+    }
+
+    #[test]
+    fn unterminated_strings_run_to_the_end() {
+        let (tokens, errors) = lex("?x: String = \"open\n?y");
+        assert_eq!(errors, vec![LexErrorKind::UnterminatedString]);
+        assert_eq!(tokens.last(), Some(&(TokenType::Error, "\"open\n?y".to_string())));
+    }
+
+    #[test]
+    fn template_strings_split_around_interpolations() {
+        let (tokens, errors) = lex("`a ${x} \\` \\${y}`");
+        let kinds: Vec<TokenType> = tokens.iter().map(|t| t.0).collect();
+        assert_eq!(kinds, vec![
+            TokenType::BackQuote, TokenType::TemplateText, TokenType::DollarBrace, TokenType::Identifier,
+            TokenType::RBrace, TokenType::TemplateText, TokenType::BackQuote,
+        ]);
+        assert_eq!(tokens[5].1, " ` ${y}");
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn braces_inside_interpolations_do_not_end_them() {
+        let (tokens, _) = lex("`${ {1: 2}[1] }!`");
+        assert_eq!(tokens.iter().filter(|t| t.0 == TokenType::TemplateText).count(), 1);
+        assert_eq!(tokens[tokens.len() - 2], (TokenType::TemplateText, "!".to_string()));
+    }
+
+    #[test]
+    fn unterminated_templates_are_reported() {
+        assert_eq!(lex("`open").1, vec![LexErrorKind::UnterminatedTemplate]);
+    }
+}
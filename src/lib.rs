@@ -0,0 +1,99 @@
+pub mod lexer;
+pub mod parser;
+pub mod checker;
+pub mod interpreter;
+pub mod error;
+pub mod diagnostics;
+
+use crate::checker::checker::Checker;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::value::Value;
+use crate::lexer::lex_error::LexError;
+use crate::lexer::token::{Lexer, Token, TokenType};
+use crate::parser::expression_ast::Expr;
+use crate::parser::lookup::BindingPower;
+use crate::parser::parse_error::ErrorCode;
+use crate::parser::parse_expression::parse_expr;
+use crate::parser::parser::Parser;
+use crate::parser::statement_ast::Stmt;
+
+pub use crate::diagnostics::diagnostic::Diagnostic;
+pub use crate::diagnostics::render::{ErrorFormat, Renderer};
+pub use crate::error::Error;
+
+/// Stack size a thread needs to run the deepest programs the parser and
+/// interpreter accept (see `interpreter::interpreter::MAX_DEPTH`). The main
+/// thread usually has less, so `lang` does its work on a thread this big.
+pub const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// Split `source` into tokens, ending with EOF.
+pub fn lex(source: &str) -> Result<Vec<Token>, Vec<LexError>> {
+    let mut lexer = Lexer::new(source.to_string());
+    let tokens = lexer.tokenize();
+    let errors = lexer.take_errors();
+    if errors.is_empty() {
+        Ok(tokens)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Parse a whole program into a `Stmt::Program`. Every lexical and syntax
+/// error in the source is reported, in that order.
+pub fn parse_program(source: &str) -> Result<Stmt, Vec<Error>> {
+    let (tokens, mut errors) = lex_all(source);
+    let mut parser = Parser::new(tokens);
+    let (program, parse_errors) = parser.parse_recovering();
+    errors.extend(parse_errors.into_iter().map(Error::from));
+    if errors.is_empty() {
+        Ok(program)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Parse a single expression; anything after it is an error.
+pub fn parse_expression(source: &str) -> Result<Expr, Vec<Error>> {
+    let (tokens, mut errors) = lex_all(source);
+    let mut parser = Parser::new(tokens);
+    parser.clean_newline();
+    let expr = match parse_expr(&mut parser, BindingPower::Default) {
+        Ok(expr) => Some(expr),
+        Err(err) => {
+            errors.push(Error::Parse(err));
+            None
+        }
+    };
+    parser.clean_newline();
+    if expr.is_some() && parser.current_tokenkind() != TokenType::EOF {
+        let found = format!("Expected the end of the expression but received {:?}", parser.current_tokenkind());
+        errors.push(Error::Parse(parser.throw(ErrorCode::UnexpectedToken, &found)));
+    }
+    match expr {
+        Some(expr) if errors.is_empty() => Ok(expr),
+        _ => Err(errors),
+    }
+}
+
+/// Parse a program and run the semantic checks on it.
+pub fn check_program(source: &str) -> Result<Stmt, Vec<Error>> {
+    let program = parse_program(source)?;
+    match Checker::new().check(&program) {
+        Ok(()) => Ok(program),
+        Err(errors) => Err(errors.into_iter().map(Error::from).collect()),
+    }
+}
+
+/// Check and then run a program, returning the value of its last statement.
+pub fn run(source: &str) -> Result<Value, Vec<Error>> {
+    let program = check_program(source)?;
+    Interpreter::new().run(&program).map_err(|err| vec![Error::from(err)])
+}
+
+// Tokens of `source` along with its lexical errors, which are not fatal
+fn lex_all(source: &str) -> (Vec<Token>, Vec<Error>) {
+    let mut lexer = Lexer::new(source.to_string());
+    let tokens = lexer.tokenize();
+    let errors = lexer.take_errors().into_iter().map(Error::from).collect();
+    (tokens, errors)
+}
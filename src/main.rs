@@ -1,5 +1,125 @@
+use lang::lexer::token::Lexer;
+use lang::{Diagnostic, ErrorFormat, Renderer};
+use std::io::{IsTerminal, Read, Write};
+use std::process::ExitCode;
 
+const USAGE: &str = "Usage: lang <command> [options] [file]
 
-fn main(){
-    print!("Hello Worlds")
-}
\ No newline at end of file
+Commands:
+    tokens  Print the tokens of the program
+    ast     Print the syntax tree of the program
+    check   Parse the program and run the semantic checks
+    run     Check and run the program
+
+Options:
+    --error-format=<human|json>  How errors are printed (default: human)
+    --color=<auto|always|never>  Color errors printed for humans (default: auto)
+
+Reads from standard input when no file, or `-`, is given.";
+
+fn main() -> ExitCode {
+    let mut format = ErrorFormat::Human;
+    let mut color = None;
+    let mut args = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--error-format=human" => format = ErrorFormat::Human,
+            "--error-format=json" => format = ErrorFormat::Json,
+            "--color=auto" => color = None,
+            "--color=always" => color = Some(true),
+            "--color=never" => color = Some(false),
+            "-h" | "--help" => args.insert(0, arg),
+            _ if arg.starts_with("--") => return usage(),
+            _ => args.push(arg),
+        }
+    }
+    let (command, path) = match args.as_slice() {
+        [command] => (command.as_str(), "-"),
+        [command, path] => (command.as_str(), path.as_str()),
+        _ => return usage(),
+    };
+    if matches!(command, "-h" | "--help" | "help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let source = match read_source(path) {
+        Ok(n) => n,
+        Err(err) => {
+            eprintln!("lang: cannot read {}: {}", path, err);
+            return ExitCode::from(2);
+        }
+    };
+
+    if !matches!(command, "tokens" | "ast" | "check" | "run") {
+        return usage();
+    }
+    // Deeply nested programs need more stack than the main thread has
+    let result = std::thread::scope(|scope| {
+        std::thread::Builder::new()
+            .stack_size(lang::STACK_SIZE)
+            .spawn_scoped(scope, || match command {
+                "tokens" => tokens(&source),
+                "ast" => lang::parse_program(&source).map(|program| println!("{:#?}", program)),
+                "check" => lang::check_program(&source).map(|_| ()),
+                _ => lang::run(&source).map(|_| ()),
+            })
+            .expect("cannot start the thread running the program")
+            .join()
+    });
+    let result = match result {
+        Ok(result) => result,
+        Err(panic) => std::panic::resume_unwind(panic),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(errors) => {
+            // Follow the NO_COLOR convention unless asked for colors
+            let color = color.unwrap_or_else(|| {
+                std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+            });
+            let name = if path == "-" { "<stdin>" } else { path };
+            let renderer = Renderer::new(name, &source).with_color(color && format == ErrorFormat::Human);
+            for (i, err) in errors.iter().enumerate() {
+                if i > 0 && format == ErrorFormat::Human {
+                    eprintln!();
+                }
+                let rendered = renderer.render(&Diagnostic::from(err), format);
+                eprintln!("{}", rendered.trim_end());
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn usage() -> ExitCode {
+    eprintln!("{}", USAGE);
+    ExitCode::from(2)
+}
+
+fn read_source(path: &str) -> std::io::Result<String> {
+    if path == "-" {
+        let mut source = String::new();
+        std::io::stdin().read_to_string(&mut source)?;
+        return Ok(source);
+    }
+    std::fs::read_to_string(path)
+}
+
+// Print every token, including those the lexer could not read
+fn tokens(source: &str) -> Result<(), Vec<lang::Error>> {
+    let mut lexer = Lexer::new(source.to_string());
+    let mut out = std::io::stdout().lock();
+    for token in lexer.by_ref() {
+        // Stop quietly when the reader goes away (e.g. `lang tokens | head`)
+        if writeln!(out, "{}:{}\t{:?}\t{:?}", token.span.line, token.span.column, token.kind, token.value).is_err() {
+            break;
+        }
+    }
+    let errors = lexer.take_errors();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.into_iter().map(lang::Error::from).collect())
+    }
+}
@@ -0,0 +1,181 @@
+use crate::interpreter::builtins::is_builtin;
+use crate::parser::expression_ast::Expr;
+use crate::parser::statement_ast::Stmt;
+use std::collections::HashSet;
+
+// Capture analysis for lambdas: the names a lambda body reads or writes
+// without declaring them itself belong to the scopes around it.
+
+/// Names used by a lambda with these `parameters` and `body` that are not
+/// declared inside it, in order of first use. Builtins are not captured.
+pub fn captures(parameters: &[Stmt], body: &[Stmt]) -> Vec<String> {
+    let mut walker = Captures { scopes: vec![HashSet::new()], found: Vec::new() };
+    parameters.iter().for_each(|p| walker.stmt(p));
+    walker.block(body);
+    walker.found
+}
+
+struct Captures {
+    scopes: Vec<HashSet<String>>,
+    found: Vec<String>,
+}
+
+impl Captures {
+    fn bind(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string());
+        }
+    }
+
+    fn use_name(&mut self, name: &str) {
+        if !is_builtin(name) && !self.scopes.iter().any(|s| s.contains(name)) && !self.found.iter().any(|n| n == name) {
+            self.found.push(name.to_string());
+        }
+    }
+
+    // Run `f` in a new scope
+    fn scoped(&mut self, f: impl FnOnce(&mut Self)) {
+        self.scopes.push(HashSet::new());
+        f(self);
+        self.scopes.pop();
+    }
+
+    fn block(&mut self, body: &[Stmt]) {
+        self.scoped(|walker| {
+            // Functions are visible in the whole block that declares them
+            for stmt in body {
+                if let Stmt::FunctionDecl { name, .. } = stmt {
+                    walker.bind(name);
+                }
+            }
+            body.iter().for_each(|s| walker.stmt(s));
+        });
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expression { expression } => self.expr(expression),
+            Stmt::Block { body } | Stmt::Program { body } => self.block(body),
+            // As in the checker, a lambda may call the variable it initializes
+            Stmt::VariableDecl { name, value, .. } if matches!(value.as_ref(), Expr::Lambda { .. }) => {
+                self.bind(name);
+                self.expr(value);
+            }
+            Stmt::VariableDecl { name, value, .. } => {
+                self.expr(value);
+                self.bind(name);
+            }
+            Stmt::FunctionDecl { parameters, body, .. } => self.scoped(|walker| {
+                parameters.iter().for_each(|p| walker.stmt(p));
+                walker.block(body.as_deref().unwrap_or_default());
+            }),
+            Stmt::Parameter { name, default, .. } => {
+                if let Some(default) = default {
+                    self.expr(default);
+                }
+                self.bind(name);
+            }
+            Stmt::Error { .. } => (),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Identifier { value, .. } => self.use_name(value),
+            Expr::AssignmentExpr { assigne, value } | Expr::CompoundAssignmentExpr { assigne, value, .. } => {
+                self.expr(assigne);
+                self.expr(value);
+            }
+            Expr::BinaryExpr { left, right, .. } | Expr::RangeExpr { start: left, end: right } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::UnaryExpr { right: value, .. } | Expr::Return { value } | Expr::SpreadExpr { value } => self.expr(value),
+            Expr::BreakExpr { value, .. } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            Expr::CallExpr { args, caller, .. } | Expr::NCallExpr { args, caller, .. } => {
+                self.expr(caller);
+                args.iter().for_each(|a| self.expr(a));
+            }
+            // Only the object is looked up, the property is a name on it
+            Expr::MembreExpr { member, .. } => self.expr(member),
+            Expr::ComputedExpr { member, property } => {
+                self.expr(member);
+                self.expr(property);
+            }
+            Expr::ForExpr { var_names, sequence, body, .. } => {
+                self.expr(sequence);
+                self.scoped(|walker| {
+                    var_names.iter().for_each(|n| walker.bind(n));
+                    walker.block(body);
+                });
+            }
+            Expr::WhileExpr { condition, body, .. } => {
+                self.expr(condition);
+                self.block(body);
+            }
+            Expr::LoopExpr { body, .. } => self.block(body),
+            Expr::IfExpr { body, else_branch } => {
+                for (condition, branch) in body {
+                    self.expr(condition);
+                    self.block(branch);
+                }
+                self.block(else_branch);
+            }
+            // What a nested lambda captures from outside this one is captured here too
+            Expr::Lambda { captures, .. } => captures.iter().for_each(|n| self.use_name(n)),
+            Expr::List { value, .. } | Expr::TemplateString { parts: value } => value.iter().for_each(|e| self.expr(e)),
+            Expr::Map { entries } => {
+                for (key, value) in entries {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            Expr::Property { value, .. } => self.expr(value),
+            Expr::TernaryExpr { condition, true_value, false_value } => {
+                self.expr(condition);
+                self.expr(true_value);
+                self.expr(false_value);
+            }
+            Expr::Boolean { .. }
+            | Expr::ContinueExpr { .. }
+            | Expr::Error { .. }
+            | Expr::Null
+            | Expr::Number { .. }
+            | Expr::String { .. } => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::expression_ast::Expr;
+
+    // Captures of the lambda `source`
+    fn captured(source: &str) -> Vec<String> {
+        match crate::parse_expression(source) {
+            Ok(Expr::Lambda { captures, .. }) => captures,
+            other => panic!("{} is not a lambda: {:?}", source, other),
+        }
+    }
+
+    #[test]
+    fn a_recursive_inner_lambda_captures_nothing() {
+        assert_eq!(captured("lambda || {\n    ?g: Any = lambda |n| n < 1 ? 0 : g(n - 1)\n    g(3)\n}"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn only_outer_names_are_captured_in_order_of_first_use() {
+        assert_eq!(captured("lambda |x| print(x + y)"), ["y"]);
+        assert_eq!(captured("lambda |a| {\n    ?b: Number = a + c\n    b + c + d + len(e)\n}"), ["c", "d", "e"]);
+    }
+
+    #[test]
+    fn nested_lambdas_pass_their_captures_up() {
+        assert_eq!(captured("lambda |x| lambda |y| x + y + z"), ["z"]);
+    }
+}
+
@@ -0,0 +1,273 @@
+use crate::lexer::token::{Lexer, Token, TokenType};
+use crate::lexer::lex_error::LexError;
+use crate::parser::expression_ast::Expr;
+use crate::parser::parse_error::ParseError;
+use crate::parser::parser::Parser;
+use crate::parser::statement_ast::Stmt;
+
+// Lossless concrete syntax tree. Every token, with the whitespace and
+// comments before it, is kept in source order, so `SyntaxNode::text`
+// gives back the exact input. The AST can be derived from any node.
+
+/// Kind of a node in the concrete syntax tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    Program,
+    Block,
+    ExpressionStmt,
+    FunctionDecl,
+    Parameter,
+    VariableDecl,
+    AssignmentExpr,
+    CompoundAssignmentExpr,
+    BinaryExpr,
+    Boolean,
+    BreakExpr,
+    CallExpr,
+    ComputedExpr,
+    ContinueExpr,
+    ForExpr,
+    Identifier,
+    IfExpr,
+    Lambda,
+    List,
+    LoopExpr,
+    Map,
+    MemberExpr,
+    NCallExpr,
+    Null,
+    Number,
+    Property,
+    RangeExpr,
+    Return,
+    SpreadExpr,
+    String,
+    TemplateString,
+    TernaryExpr,
+    UnaryExpr,
+    WhileExpr,
+    Error,
+}
+
+impl SyntaxKind {
+    pub fn of_stmt(stmt: &Stmt) -> Self {
+        match stmt {
+            Stmt::Expression { .. } => SyntaxKind::ExpressionStmt,
+            Stmt::Block { .. } => SyntaxKind::Block,
+            Stmt::Program { .. } => SyntaxKind::Program,
+            Stmt::FunctionDecl { .. } => SyntaxKind::FunctionDecl,
+            Stmt::Parameter { .. } => SyntaxKind::Parameter,
+            Stmt::VariableDecl { .. } => SyntaxKind::VariableDecl,
+            Stmt::Error { .. } => SyntaxKind::Error,
+        }
+    }
+
+    pub fn of_expr(expr: &Expr) -> Self {
+        match expr {
+            Expr::AssignmentExpr { .. } => SyntaxKind::AssignmentExpr,
+            Expr::CompoundAssignmentExpr { .. } => SyntaxKind::CompoundAssignmentExpr,
+            Expr::BinaryExpr { .. } => SyntaxKind::BinaryExpr,
+            Expr::Boolean { .. } => SyntaxKind::Boolean,
+            Expr::Error { .. } => SyntaxKind::Error,
+            Expr::BreakExpr { .. } => SyntaxKind::BreakExpr,
+            Expr::CallExpr { .. } => SyntaxKind::CallExpr,
+            Expr::ComputedExpr { .. } => SyntaxKind::ComputedExpr,
+            Expr::ContinueExpr { .. } => SyntaxKind::ContinueExpr,
+            Expr::ForExpr { .. } => SyntaxKind::ForExpr,
+            Expr::Identifier { .. } => SyntaxKind::Identifier,
+            Expr::IfExpr { .. } => SyntaxKind::IfExpr,
+            Expr::Lambda { .. } => SyntaxKind::Lambda,
+            Expr::List { .. } => SyntaxKind::List,
+            Expr::LoopExpr { .. } => SyntaxKind::LoopExpr,
+            Expr::Map { .. } => SyntaxKind::Map,
+            Expr::MembreExpr { .. } => SyntaxKind::MemberExpr,
+            Expr::NCallExpr { .. } => SyntaxKind::NCallExpr,
+            Expr::Null => SyntaxKind::Null,
+            Expr::Number { .. } => SyntaxKind::Number,
+            Expr::Property { .. } => SyntaxKind::Property,
+            Expr::RangeExpr { .. } => SyntaxKind::RangeExpr,
+            Expr::Return { .. } => SyntaxKind::Return,
+            Expr::SpreadExpr { .. } => SyntaxKind::SpreadExpr,
+            Expr::String { .. } => SyntaxKind::String,
+            Expr::TemplateString { .. } => SyntaxKind::TemplateString,
+            Expr::TernaryExpr { .. } => SyntaxKind::TernaryExpr,
+            Expr::UnaryExpr { .. } => SyntaxKind::UnaryExpr,
+            Expr::WhileExpr { .. } => SyntaxKind::WhileExpr,
+        }
+    }
+}
+
+/// Recorded by the parser as it goes; turned into a tree by `build`.
+/// A node is only known to start at a checkpoint once it is complete, so
+/// each checkpoint reserves a `Tombstone` that becomes the node's `Start`.
+/// Another node wrapped around it later takes over that slot and records
+/// the inner `Start`, moved to the end, as `inner`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Tombstone, // A checkpoint no node started at (yet)
+    Start { kind: SyntaxKind, inner: Option<usize> },
+    Token,     // The next token was consumed
+    Finish,
+}
+
+/// A token together with its exact source text and leading trivia.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxToken {
+    pub token: Token,
+    pub leading: String, // Whitespace and comments before the token, verbatim
+    pub text: String,    // The token as written, e.g. `"a\n"` rather than its value
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode {
+    /// The exact source text covered by the node.
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        for token in self.tokens() {
+            out.push_str(&token.leading);
+            out.push_str(&token.text);
+        }
+        out
+    }
+
+    /// All tokens of the node, in source order.
+    pub fn tokens(&self) -> Vec<&SyntaxToken> {
+        let mut tokens = Vec::new();
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(n) => tokens.extend(n.tokens()),
+                SyntaxElement::Token(t) => tokens.push(t),
+            }
+        }
+        tokens
+    }
+
+    /// Direct child nodes, skipping tokens.
+    pub fn child_nodes(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.children.iter().filter_map(|c| match c {
+            SyntaxElement::Node(n) => Some(n),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /// Parse the node's tokens into the abstract syntax tree. The result
+    /// is always a `Stmt::Program`, holding a single statement for
+    /// statement nodes.
+    pub fn to_ast(&self) -> Result<Stmt, Vec<ParseError>> {
+        let tokens = self.tokens().into_iter()
+            .map(|t| t.token.clone())
+            .filter(|t| t.kind != TokenType::EOF)
+            .collect();
+        Parser::new(tokens).parse()
+    }
+}
+
+/// Assemble the tree from the parser's events and the tokens it read.
+/// Tokens left over after the last event (at least the EOF) go to the root.
+pub fn build(source: &str, tokens: &[Token], events: &[Event]) -> SyntaxNode {
+    let mut stack = vec![SyntaxNode { kind: SyntaxKind::Program, children: Vec::new() }];
+    let mut tokens = tokens.iter();
+    let mut events = events.to_vec();
+    for i in 0..events.len() {
+        match std::mem::replace(&mut events[i], Event::Tombstone) {
+            Event::Tombstone => (),
+            Event::Start { kind, mut inner } => {
+                stack.push(SyntaxNode { kind, children: Vec::new() });
+                // Nodes starting at the same place open outermost first
+                while let Some(j) = inner {
+                    let Event::Start { kind, inner: next } = std::mem::replace(&mut events[j], Event::Tombstone) else {
+                        unreachable!("`inner` always points at a Start");
+                    };
+                    stack.push(SyntaxNode { kind, children: Vec::new() });
+                    inner = next;
+                }
+            }
+            Event::Token => {
+                if let Some(token) = tokens.next() {
+                    let token = syntax_token(source, token);
+                    stack.last_mut().unwrap().children.push(SyntaxElement::Token(token));
+                }
+            }
+            Event::Finish => {
+                let node = stack.pop().unwrap();
+                stack.last_mut().unwrap().children.push(SyntaxElement::Node(node));
+            }
+        }
+    }
+    let mut root = stack.swap_remove(0);
+    root.children.extend(tokens.map(|t| SyntaxElement::Token(syntax_token(source, t))));
+    root
+}
+
+fn syntax_token(source: &str, token: &Token) -> SyntaxToken {
+    let start = token.trivia.first().map_or(token.span.start, |t| t.span.start);
+    SyntaxToken {
+        token: token.clone(),
+        leading: source[start..token.span.start].to_string(),
+        text: source[token.span.start..token.span.end].to_string(),
+    }
+}
+
+/// Lex and parse `source` into a lossless tree, along with every error
+/// found. The tree is complete even when there are errors.
+pub fn parse_cst(source: &str) -> (SyntaxNode, Vec<LexError>, Vec<ParseError>) {
+    let mut lexer = Lexer::new(source.to_string());
+    let tokens = lexer.tokenize();
+    let lex_errors = lexer.take_errors();
+    let mut parser = Parser::new(tokens);
+    let (_, parse_errors) = parser.parse_recovering();
+    let root = build(source, &parser.tokens, &parser.events);
+    (root, lex_errors, parse_errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_lossless(source: &str) {
+        let (root, _, _) = parse_cst(source);
+        assert_eq!(root.text(), source);
+    }
+
+    #[test]
+    fn the_tree_keeps_every_character() {
+        assert_lossless("?x: Number = 1 + 2 * 3\nprint(x)\n");
+        assert_lossless("  \t?a: [Number] = [1,  2 ,3]   \n\n\nfunc f|x: Number, y: Number = 2| {\n\treturn x+y\n}\n");
+        assert_lossless("?s: String = `a ${1 + 2} b`\n");
+    }
+
+    #[test]
+    fn the_tree_keeps_comments() {
+        assert_lossless("// line\n/* block /* nested */ */ ?x: Number = 1 // trailing\n/// doc\nfunc f|| {}\n");
+    }
+
+    #[test]
+    fn the_tree_keeps_code_that_failed_to_parse() {
+        assert_lossless("?x: = 1\nprint(2 $ 3)\nfunc f|a: Number| {\n    ?y: Number = )\n}\n");
+        assert_lossless("?x: Number = (1 + \n");
+        assert_lossless("\"unterminated\n");
+    }
+
+    #[test]
+    fn nodes_wrapped_at_the_same_place_nest() {
+        let (root, _, _) = parse_cst("a + b + c\n");
+        let stmt = root.child_nodes().next().unwrap();
+        let outer = stmt.child_nodes().next().unwrap();
+        assert_eq!(outer.kind, SyntaxKind::BinaryExpr);
+        assert_eq!(outer.text(), "a + b + c");
+        let inner = outer.child_nodes().next().unwrap();
+        assert_eq!(inner.kind, SyntaxKind::BinaryExpr);
+        assert_eq!(inner.text(), "a + b");
+    }
+}
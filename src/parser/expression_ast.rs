@@ -1,4 +1,5 @@
 use crate::parser::statement_ast::{Stmt};
+use crate::lexer::token::Span;
 
 // First, define the Token type (assuming it's needed for Binary/Unary expressions)
 #[derive(Debug, Clone, PartialEq)]
@@ -13,7 +14,12 @@ pub struct Token {
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     AssignmentExpr {
-        assigne: Box<Expr>, // Can be Identifier or Membre
+        assigne: Box<Expr>, // Can be Identifier, Membre or Computed
+        value: Box<Expr>,
+    },
+    CompoundAssignmentExpr {
+        assigne: Box<Expr>, // As for AssignmentExpr, but evaluated only once
+        operator: Token,    // The binary operator, e.g. `+` for `+=`
         value: Box<Expr>,
     },
     BinaryExpr {
@@ -24,29 +30,50 @@ pub enum Expr {
     Boolean {
         value: bool,
     },
+    BreakExpr {
+        label: Option<String>,     // `outer` in `break 'outer`
+        value: Option<Box<Expr>>, // Only a `loop` can be left with a value
+        span: Span,                // The `break` keyword
+    },
+    Error {
+        span: Span, // Source of an expression that failed to parse
+    },
     CallExpr {
         args: Vec<Expr>,
         caller: Box<Expr>,
+        span: Span, // From the callee to the closing parenthesis
+    },
+    ContinueExpr {
+        label: Option<String>,
+        span: Span, // The `continue` keyword
     },
     ComputedExpr {
         member: Box<Expr>,
         property: Box<Expr>,
     },
     ForExpr {
-        var_name: Box<Expr>, // IdentifierExpr
-        sequence: Box<Expr>, // IdentifierExpr | Membre | ListExpr
-        iterator: Box<Expr>, // Call
+        var_names: Vec<String>, // `x` in `for x in ..`, or `k, v` in `for k, v in ..`
+        sequence: Box<Expr>,    // Anything iterable: list, map, range or string
+        body: Vec<Stmt>,
+        label: Option<String>,
+        span: Span, // The loop variables
     },
     Identifier {
         value: String,
+        span: Span,
     },
     IfExpr {
         body: Vec<(Expr, Vec<Stmt>)>, // Vector of tuples (condition, statements)
-        else_branch: Vec<Expr>,      // Else expressions
+        else_branch: Vec<Stmt>,      // Statements of the final `else`, if any
     },
     Lambda {
         parameters: Vec<Stmt>,
-        body: Box<Expr>,
+        body: Vec<Stmt>,       // `x + y` in `lambda |x, y| x + y` is a single statement
+        captures: Vec<String>, // Names the body uses from the scopes around it
+    },
+    LoopExpr {
+        body: Vec<Stmt>,
+        label: Option<String>, // `outer` in `'outer: loop { }`
     },
     List {
         value: Vec<Expr>,
@@ -61,8 +88,9 @@ pub enum Expr {
         property: Box<Expr>, // IdentifierExpr
     },
     NCallExpr {
-        args: Vec<Stmt>,
+        args: Vec<Expr>, // Positional arguments, then a `Property` per named one
         caller: Box<Expr>,
+        span: Span,
     },
     Null,
     Number {
@@ -73,13 +101,23 @@ pub enum Expr {
         name: String,
         value: Box<Expr>,
     },
+    RangeExpr {
+        start: Box<Expr>,
+        end: Box<Expr>, // Excluded from the range
+    },
     Return {
         value: Box<Expr>,
     },
+    SpreadExpr {
+        value: Box<Expr>, // `xs` in `f(...xs)`, passed as one argument per item
+    },
     String {
         value: String,
         length: usize,
     },
+    TemplateString {
+        parts: Vec<Expr>, // String pieces and interpolated expressions, in order
+    },
     TernaryExpr {
         condition: Box<Expr>,
         true_value: Box<Expr>,
@@ -92,6 +130,7 @@ pub enum Expr {
     WhileExpr {
         condition: Box<Expr>,
         body: Vec<Stmt>,
+        label: Option<String>,
     },
 }
 
@@ -121,6 +160,16 @@ impl Expr {
         Expr::Boolean { value }
     }
 
+    /// Where the expression starts, if it or the expression it begins
+    /// with carries a span.
+    pub fn start_span(&self) -> Option<Span> {
+        match self {
+            Expr::Identifier { span, .. } | Expr::CallExpr { span, .. } | Expr::NCallExpr { span, .. } | Expr::Error { span } => Some(*span),
+            Expr::MembreExpr { member, .. } | Expr::ComputedExpr { member, .. } => member.start_span(),
+            _ => None,
+        }
+    }
+
     // Add more constructor functions as needed...
 }
 
@@ -2,11 +2,13 @@ use crate::parser::parser::Parser;
 use crate::parser::expression_ast::*;
 use crate::parser::statement_ast::*;
 use crate::lexer::token::{TokenType, KEYWORDS};
-use crate::parser::parse_Statement::*;
+use crate::parser::parse_statement::*;
+use crate::parser::parse_error::ParseResult;
+use crate::parser::parse_expression::*;
 use std::collections::HashMap;
 use once_cell::sync::Lazy;
 
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum BindingPower {
     Default,
     Comma,
@@ -14,6 +16,7 @@ pub enum BindingPower {
     Conditional,//ternary
     Logical,
     Relational,
+    Range,
     Additive,
     Multiplicative,
     Unary,
@@ -22,9 +25,9 @@ pub enum BindingPower {
     Primary
 }
 
-type StmtHandler = fn(p: &mut Parser) -> Stmt;
-type NudHandler = fn(p: &mut Parser) -> Stmt;
-type LedHandler = fn(p: &mut Parser, left: Expr, bp: BindingPower) -> Expr;
+type StmtHandler = fn(p: &mut Parser) -> ParseResult<Stmt>;
+type NudHandler = fn(p: &mut Parser) -> ParseResult<Expr>;
+type LedHandler = fn(p: &mut Parser, left: Expr, bp: BindingPower) -> ParseResult<Expr>;
 
 // Define type aliases for the lookup tables
 pub type BpLu = HashMap<TokenType, BindingPower>;
@@ -36,6 +39,14 @@ pub static BP_LU: Lazy<HashMap<TokenType, BindingPower>> = Lazy::new(|| {
     let mut m = HashMap::new();
 
     m.insert(TokenType::Equal, BindingPower::Assignment);
+    m.insert(TokenType::PlusEqual, BindingPower::Assignment);
+    m.insert(TokenType::MinusEqual, BindingPower::Assignment);
+    m.insert(TokenType::StarEqual, BindingPower::Assignment);
+    m.insert(TokenType::SlashEqual, BindingPower::Assignment);
+    m.insert(TokenType::PercentEqual, BindingPower::Assignment);
+    m.insert(TokenType::AmperEqual, BindingPower::Assignment);
+    m.insert(TokenType::VerbarEqual, BindingPower::Assignment);
+    m.insert(TokenType::CircumFlexEqual, BindingPower::Assignment);
     m.insert(TokenType::AmperAmper, BindingPower::Logical);
     m.insert(TokenType::VerbarVerbar, BindingPower::Logical);
     m.insert(TokenType::Inf, BindingPower::Relational);
@@ -44,6 +55,7 @@ pub static BP_LU: Lazy<HashMap<TokenType, BindingPower>> = Lazy::new(|| {
     m.insert(TokenType::InfEqual, BindingPower::Relational);
     m.insert(TokenType::EqualEqual, BindingPower::Relational);
     m.insert(TokenType::ExclEqual, BindingPower::Relational);
+    m.insert(TokenType::DotDot, BindingPower::Range);
     m.insert(TokenType::Minus, BindingPower::Additive);
     m.insert(TokenType::Plus, BindingPower::Additive);
     m.insert(TokenType::Star, BindingPower::Multiplicative);
@@ -58,31 +70,49 @@ pub static BP_LU: Lazy<HashMap<TokenType, BindingPower>> = Lazy::new(|| {
    
     // LITERALS AND IDENTIFIER
     m.insert(TokenType::Identifier, BindingPower::Primary);
-    m.insert(TokenType::DoubleQuote, BindingPower::Primary);
+    m.insert(TokenType::String, BindingPower::Primary);
+    m.insert(TokenType::BackQuote, BindingPower::Primary);
+    m.insert(TokenType::True, BindingPower::Primary);
+    m.insert(TokenType::False, BindingPower::Primary);
+    m.insert(TokenType::Null, BindingPower::Primary);
+    m.insert(TokenType::SelfRef, BindingPower::Primary);
+    m.insert(TokenType::Case, BindingPower::Primary);
+    m.insert(TokenType::Default, BindingPower::Primary);
+    m.insert(TokenType::Public, BindingPower::Primary);
     m.insert(TokenType::Number, BindingPower::Primary);
-    m.insert(TokenType::LBracket, BindingPower::Primary);
 
-    // UNARY & PREFIX
-    m.insert(TokenType::Plus, BindingPower::Primary);
-    m.insert(TokenType::Minus, BindingPower::Primary);
-    m.insert(TokenType::ExclamationMark, BindingPower::Primary);
-
-    // GROUPING EXPR
-    m.insert(TokenType::LParen, BindingPower::Primary);
+    // GROUPING EXPR (`[` and `(` are bound above as postfix operators)
     m.insert(TokenType::Lambda, BindingPower::Primary);
     m.insert(TokenType::Return, BindingPower::Primary);
+    m.insert(TokenType::IfConditional, BindingPower::Primary);
+    m.insert(TokenType::ForLoop, BindingPower::Primary);
+    m.insert(TokenType::WhileLoop, BindingPower::Primary);
+    m.insert(TokenType::BreakLoop, BindingPower::Primary);
+    m.insert(TokenType::Quote, BindingPower::Primary);
+    m.insert(TokenType::Break, BindingPower::Primary);
+    m.insert(TokenType::Continue, BindingPower::Primary);
 
     m
 });
 pub static NUD_LU: Lazy<HashMap<TokenType, NudHandler>> = Lazy::new(|| {
-    let mut m = HashMap::new();
+    let mut m: NudLu = HashMap::new();
         // ASSIGNMENT
    
     // LITERALS AND IDENTIFIER
     m.insert(TokenType::Identifier, parse_primary_expr);
-    m.insert(TokenType::DoubleQuote, parse_primary_expr);
+    m.insert(TokenType::String, parse_primary_expr);
+    m.insert(TokenType::BackQuote, parse_template_expr);
+    m.insert(TokenType::Error, parse_error_expr);
+    m.insert(TokenType::True, parse_primary_expr);
+    m.insert(TokenType::False, parse_primary_expr);
+    m.insert(TokenType::Null, parse_primary_expr);
+    m.insert(TokenType::SelfRef, parse_primary_expr);
+    m.insert(TokenType::Case, parse_primary_expr);
+    m.insert(TokenType::Default, parse_primary_expr);
+    m.insert(TokenType::Public, parse_primary_expr);
     m.insert(TokenType::Number, parse_primary_expr);
     m.insert(TokenType::LBracket, parse_array_expr);
+    m.insert(TokenType::LBrace, parse_map_expr);
 
     // UNARY & PREFIX
     m.insert(TokenType::Plus, parse_prefix_expr);
@@ -93,13 +123,28 @@ pub static NUD_LU: Lazy<HashMap<TokenType, NudHandler>> = Lazy::new(|| {
     m.insert(TokenType::LParen, parse_grouping_expr);
     m.insert(TokenType::Lambda, parse_lambda_expr);
     m.insert(TokenType::Return, parse_return_decl);
+    m.insert(TokenType::IfConditional, parse_if_expr);
+    m.insert(TokenType::ForLoop, parse_for_expr);
+    m.insert(TokenType::WhileLoop, parse_while_expr);
+    m.insert(TokenType::BreakLoop, parse_loop_expr);
+    m.insert(TokenType::Quote, parse_labelled_expr);
+    m.insert(TokenType::Break, parse_break_expr);
+    m.insert(TokenType::Continue, parse_continue_expr);
     m
 });
 
 pub static LED_LU: Lazy<HashMap<TokenType, LedHandler>> = Lazy::new(|| {
-    let mut m = HashMap::new();
+    let mut m: LedLu = HashMap::new();
 
     m.insert(TokenType::Equal, parse_assignment_expr);
+    m.insert(TokenType::PlusEqual, parse_assignment_expr);
+    m.insert(TokenType::MinusEqual, parse_assignment_expr);
+    m.insert(TokenType::StarEqual, parse_assignment_expr);
+    m.insert(TokenType::SlashEqual, parse_assignment_expr);
+    m.insert(TokenType::PercentEqual, parse_assignment_expr);
+    m.insert(TokenType::AmperEqual, parse_assignment_expr);
+    m.insert(TokenType::VerbarEqual, parse_assignment_expr);
+    m.insert(TokenType::CircumFlexEqual, parse_assignment_expr);
 
     // LOGICAL
     m.insert(TokenType::AmperAmper,parse_binary_expr);
@@ -113,6 +158,9 @@ pub static LED_LU: Lazy<HashMap<TokenType, LedHandler>> = Lazy::new(|| {
     m.insert(TokenType::EqualEqual, parse_binary_expr);
     m.insert(TokenType::ExclEqual, parse_binary_expr);
 
+    // RANGE
+    m.insert(TokenType::DotDot, parse_range_expr);
+
     // ADDITIVE & MULTIPLICATIVE
     m.insert(TokenType::Minus, parse_binary_expr);
     m.insert(TokenType::Plus, parse_binary_expr);
@@ -120,8 +168,8 @@ pub static LED_LU: Lazy<HashMap<TokenType, LedHandler>> = Lazy::new(|| {
     m.insert(TokenType::Slash, parse_binary_expr);
     m.insert(TokenType::Percent, parse_binary_expr);
 
-    // CONDITIONAL
-    m.insert(TokenType::QuestionMark, parse_binary_expr);
+    // CONDITIONAL (`?` starting a statement is a declaration instead, see STMT_LU)
+    m.insert(TokenType::QuestionMark, parse_ternary_expr);
     
     // MEMBER, COMPUTED, CALL
     m.insert(TokenType::Dot, parse_member_expr);
@@ -140,6 +188,7 @@ pub static STMT_LU: Lazy<HashMap<TokenType, StmtHandler>> = Lazy::new(|| {
     m.insert(TokenType::IfConditional, parse_if_stmt as StmtHandler);
     m.insert(TokenType::ForLoop, parse_for_stmt as StmtHandler);
     m.insert(TokenType::WhileLoop, parse_while_stmt as StmtHandler);
+    m.insert(TokenType::LBrace, parse_block_stmt as StmtHandler);
     m.insert(TokenType::QuestionMark, parse_var as StmtHandler);
     m.insert(TokenType::ExclamationMark, parse_var as StmtHandler);
 
@@ -1,6 +1,10 @@
 pub mod expression_ast;
 pub mod statement_ast;
+#[allow(clippy::module_inception)]
 pub mod parser;
-pub mod parse_Statement;
+pub mod parse_error;
+pub mod parse_statement;
 pub mod lookup;
-pub mod parse_expression;
\ No newline at end of file
+pub mod parse_expression;
+pub mod cst;
+pub mod captures;
\ No newline at end of file
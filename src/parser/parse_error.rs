@@ -0,0 +1,86 @@
+use crate::lexer::token::{Span, TokenType};
+
+pub type ParseResult<T> = Result<T, ParseError>;
+
+/// Stable identifiers for the errors reported by the parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    UnexpectedToken,    // E0001
+    UnexpectedEof,      // E0002
+    ExpectedExpression, // E0003
+    ExpectedType,       // E0004
+    ExpectedOperator,   // E0005
+    Unsupported,        // E0006
+    InvalidAssignment,  // E0007
+    TooDeep,            // E0008
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::UnexpectedToken => "E0001",
+            ErrorCode::UnexpectedEof => "E0002",
+            ErrorCode::ExpectedExpression => "E0003",
+            ErrorCode::ExpectedType => "E0004",
+            ErrorCode::ExpectedOperator => "E0005",
+            ErrorCode::Unsupported => "E0006",
+            ErrorCode::InvalidAssignment => "E0007",
+            ErrorCode::TooDeep => "E0008",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub expected: Option<TokenType>, // Token the parser was looking for, if any
+    pub found: TokenType,            // Token actually present at `span`
+    pub span: Span,
+    pub labels: Vec<(Span, String)>, // Related places in the source, e.g. an unclosed `{`
+}
+
+impl ParseError {
+    pub fn new(code: ErrorCode, message: impl Into<String>, found: TokenType, span: Span) -> Self {
+        ParseError {
+            code,
+            message: message.into(),
+            expected: None,
+            found,
+            span,
+            labels: Vec::new(),
+        }
+    }
+
+    /// Error for a token that did not match the `expected` kind.
+    pub fn expected(expected: TokenType, found: TokenType, span: Span, message: Option<String>) -> Self {
+        let code = if found == TokenType::EOF {
+            ErrorCode::UnexpectedEof
+        } else {
+            ErrorCode::UnexpectedToken
+        };
+        let message = message.unwrap_or_else(|| format!("Expected {:?} but received {:?}", expected, found));
+        ParseError {
+            code,
+            message,
+            expected: Some(expected),
+            found,
+            span,
+            labels: Vec::new(),
+        }
+    }
+
+    /// Point at another place in the source that explains the error.
+    pub fn with_label(mut self, span: Span, label: impl Into<String>) -> Self {
+        self.labels.push((span, label.into()));
+        self
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error[{}] {}:{}: {}", self.code.as_str(), self.span.line, self.span.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
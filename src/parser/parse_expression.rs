@@ -1,22 +1,582 @@
 use crate::parser::parser::Parser;
-use crate::parser::statement_ast::*;
-use crate::lexer::token::TokenType;
+use crate::parser::expression_ast::*;
+use crate::parser::parse_error::*;
 use crate::parser::lookup::*;
+use crate::parser::parse_statement::{parse_block, parse_parameters};
+use crate::parser::captures::captures;
+use crate::parser::statement_ast::Stmt;
+use crate::parser::cst::SyntaxKind;
+use crate::lexer::token::TokenType;
 
-fn parse_expr(parser: &mut Parser, bp: BindingPower) -> Expr {
-    let func = get_nud_handler(parser);
-    if func.is_none() {
-		//throw error
+pub fn parse_expr(parser: &mut Parser, bp: BindingPower) -> ParseResult<Expr> {
+	parser.nested(|parser| {
+		let func = match get_nud_handler(parser.current_tokenkind()) {
+			Some(n) => n,
+			None => {
+				let found = format!("Expected an expression but received {:?}", parser.current_tokenkind());
+				return Err(parser.throw(ErrorCode::ExpectedExpression, &found));
+			}
+		};
+		let checkpoint = parser.checkpoint();
+		let mut left = func(parser)?;
+		parser.wrap_node(checkpoint, SyntaxKind::of_expr(&left));
+		loop {
+			// Unreadable input after an operand was already reported by the
+			// lexer; read it as an unknown operator so its right side is not
+			// reported again
+			if parser.current_tokenkind() == TokenType::Error {
+				let span = parser.advance().span;
+				if get_nud_handler(parser.current_tokenkind()).is_some() {
+					parse_expr(parser, bp)?;
+				}
+				left = Expr::Error { span };
+				parser.wrap_node(checkpoint, SyntaxKind::of_expr(&left));
+				continue;
+			}
+			if get_binding_power(parser.current_tokenkind()).is_none_or(|n| n <= bp) {
+				break;
+			}
+			let tokenkind = parser.current_tokenkind();
+			let led = match get_led_handler(tokenkind) {
+				Some(n) => n,
+				None => {
+					let found = format!("Expected an operator but received {:?}", tokenkind);
+					return Err(parser.throw(ErrorCode::ExpectedOperator, &found));
+				}
+			};
+			let power = get_binding_power(tokenkind).unwrap();
+			left = led(parser, left, power)?;
+			parser.wrap_node(checkpoint, SyntaxKind::of_expr(&left));
+		}
+		Ok(left)
+	})
+}
+
+pub fn parse_primary_expr(parser: &mut Parser) -> ParseResult<Expr> {
+	let token = parser.advance();
+	match token.kind {
+		TokenType::Number => match token.number {
+			Some(value) => Ok(Expr::Number { value }),
+			None => Err(ParseError::new(ErrorCode::ExpectedExpression, format!("Invalid number {}", token.value), token.kind, token.span)),
+		},
+		TokenType::String => Ok(Expr::String { length: token.value.chars().count(), value: token.value }),
+		TokenType::Identifier | TokenType::SelfRef => Ok(Expr::Identifier { value: token.value, span: token.span }),
+		kind if kind.is_contextual() => Ok(Expr::Identifier { value: token.value, span: token.span }),
+		TokenType::True => Ok(Expr::Boolean { value: true }),
+		TokenType::False => Ok(Expr::Boolean { value: false }),
+		TokenType::Null => Ok(Expr::Null),
+		kind => Err(ParseError::new(ErrorCode::ExpectedExpression, format!("Expected an expression but received {:?}", kind), kind, token.span)),
 	}
-	let left = (func.unwrap())(parser);
-	while (get_binding_power(parser.current_tokenkind).unwrap() > bp){
-		const tokenkind: TokenType = parser.current_tokenkind();
-		let led = get_led_handler(tokenkind);
-		if (led.is_none()){
-			//throw error
+}
+
+pub fn parse_template_expr(parser: &mut Parser) -> ParseResult<Expr> {
+	parser.nested(|parser| {
+		parser.expect(TokenType::BackQuote)?;
+		let mut parts = Vec::new();
+		loop {
+			match parser.current_tokenkind() {
+				TokenType::TemplateText => {
+					let text = parser.advance().value;
+					parts.push(Expr::String { length: text.chars().count(), value: text });
+				}
+				TokenType::DollarBrace => {
+					parser.advance();
+					parts.push(parse_expr(parser, BindingPower::Default)?);
+					parser.expect_error(TokenType::RBrace, Some("Expected '}' to close the interpolation".to_string()))?;
+				}
+				_ => break,
+			}
 		}
-		left = (led.unwrap())(parser, left, bp);
+		parser.expect_error(TokenType::BackQuote, Some("Expected '`' to close the template string".to_string()))?;
+		Ok(Expr::TemplateString { parts })
+	})
+}
+
+/// A token the lexer could not read. It was already reported, so it just
+/// becomes a placeholder expression.
+pub fn parse_error_expr(parser: &mut Parser) -> ParseResult<Expr> {
+	let token = parser.advance();
+	Ok(Expr::Error { span: token.span })
+}
+
+// Operator token as stored in the AST
+fn ast_token(token: &crate::lexer::token::Token) -> Token {
+	Token { kind: format!("{:?}", token.kind), value: token.value.clone() }
+}
+
+pub fn parse_prefix_expr(parser: &mut Parser) -> ParseResult<Expr> {
+	let operator = parser.advance();
+	let right = parse_expr(parser, BindingPower::Unary)?;
+	Ok(Expr::UnaryExpr { operator: ast_token(&operator), right: Box::new(right) })
+}
+
+pub fn parse_grouping_expr(parser: &mut Parser) -> ParseResult<Expr> {
+	let open = parser.expect(TokenType::LParen)?.span;
+	let expr = parse_expr(parser, BindingPower::Default)?;
+	parser.expect_error(TokenType::RParen, Some("Expected ')' to close the group".to_string()))
+		.map_err(|err| err.with_label(open, "group opened here"))?;
+	Ok(expr)
+}
+
+pub fn parse_binary_expr(parser: &mut Parser, left: Expr, bp: BindingPower) -> ParseResult<Expr> {
+	let operator = parser.advance();
+	let right = parse_expr(parser, bp)?;
+	Ok(Expr::new_binary(left, ast_token(&operator), right))
+}
+
+// `start..end`; the end is parsed at the same power so `a..b..c` is rejected
+pub fn parse_range_expr(parser: &mut Parser, left: Expr, bp: BindingPower) -> ParseResult<Expr> {
+	parser.advance();
+	let end = parse_expr(parser, bp)?;
+	if parser.current_tokenkind() == TokenType::DotDot {
+		return Err(parser.throw(ErrorCode::UnexpectedToken, "Ranges cannot be chained"));
+	}
+	Ok(Expr::RangeExpr { start: Box::new(left), end: Box::new(end) })
+}
+
+/// `cond ? a : b`. A `?` is only a ternary after an operand; at the start
+/// of a statement it declares a variable (`?x: Number = 1`). As an
+/// expression statement ends at the end of its line, the `?` has to be on
+/// the same line as the condition.
+///
+/// The false branch is parsed just below `Conditional` so that
+/// `a ? b : c ? d : e` groups as `a ? b : (c ? d : e)`.
+pub fn parse_ternary_expr(parser: &mut Parser, condition: Expr, _bp: BindingPower) -> ParseResult<Expr> {
+	parser.expect(TokenType::QuestionMark)?;
+	let true_value = parse_expr(parser, BindingPower::Default)?;
+	parser.expect_error(TokenType::Colon, Some("Expected ':' in the conditional expression".to_string()))?;
+	let false_value = parse_expr(parser, BindingPower::Assignment)?;
+	Ok(Expr::TernaryExpr {
+		condition: Box::new(condition),
+		true_value: Box::new(true_value),
+		false_value: Box::new(false_value),
+	})
+}
+
+/// `a = b`, or `a += b` and the other compound forms. The target must be
+/// a variable, a property or an index.
+pub fn parse_assignment_expr(parser: &mut Parser, left: Expr, _bp: BindingPower) -> ParseResult<Expr> {
+	if !matches!(left, Expr::Identifier { .. } | Expr::MembreExpr { .. } | Expr::ComputedExpr { .. }) {
+		return Err(parser.throw(ErrorCode::InvalidAssignment, "Invalid assignment target"));
+	}
+	let token = parser.advance();
+	// Parse below assignment so that `a = b = c` groups as `a = (b = c)`
+	let value = parse_expr(parser, BindingPower::Comma)?;
+	let operator = match token.kind {
+		TokenType::Equal => return Ok(Expr::new_assignment(left, value)),
+		TokenType::PlusEqual => TokenType::Plus,
+		TokenType::MinusEqual => TokenType::Minus,
+		TokenType::StarEqual => TokenType::Star,
+		TokenType::SlashEqual => TokenType::Slash,
+		TokenType::PercentEqual => TokenType::Percent,
+		TokenType::AmperEqual => TokenType::Amper,
+		TokenType::VerbarEqual => TokenType::Verbar,
+		_ => TokenType::CircumFlex,
+	};
+	// `a op= b` reads as `a = a op b`, with `a` evaluated once
+	let operator = Token { kind: format!("{:?}", operator), value: token.value.trim_end_matches('=').to_string() };
+	Ok(Expr::CompoundAssignmentExpr { assigne: Box::new(left), operator, value: Box::new(value) })
+}
+
+pub fn parse_return_decl(parser: &mut Parser) -> ParseResult<Expr> {
+	parser.expect(TokenType::Return)?;
+	let value = if parser.is_one_of_many(vec![TokenType::NewLine, TokenType::SemiColon, TokenType::RBrace, TokenType::EOF]) {
+		Expr::Null
+	} else {
+		parse_expr(parser, BindingPower::Default)?
+	};
+	Ok(Expr::Return { value: Box::new(value) })
+}
+
+/// `if cond { } else if cond { } else { }`. Its value is the value of the
+/// last statement of the branch taken, or null when no branch is taken.
+pub fn parse_if_expr(parser: &mut Parser) -> ParseResult<Expr> {
+	let mut body = Vec::new();
+	let mut else_branch = Vec::new();
+	loop {
+		parser.expect(TokenType::IfConditional)?;
+		let condition = parse_expr(parser, BindingPower::Default)?;
+		body.push((condition, parse_block_body(parser)?));
+		// `else` may start the next line
+		if parser.peek_past_newlines() != TokenType::ElseConditional {
+			break;
+		}
+		parser.clean_newline();
+		parser.advance();
+		if parser.current_tokenkind() != TokenType::IfConditional {
+			else_branch = parse_block_body(parser)?;
+			break;
+		}
+	}
+	Ok(Expr::IfExpr { body, else_branch })
+}
+
+/// `for x in seq { }` or `for k, v in seq { }`. Its value is null.
+pub fn parse_for_expr(parser: &mut Parser) -> ParseResult<Expr> {
+	parser.expect(TokenType::ForLoop)?;
+	let first = parser.expect_error(TokenType::Identifier, Some("Expected a loop variable".to_string()))?;
+	let mut var_names = vec![first.value];
+	if parser.current_tokenkind() == TokenType::Comma {
+		parser.advance();
+		var_names.push(parser.expect_error(TokenType::Identifier, Some("Expected a second loop variable".to_string()))?.value);
+	}
+	let span = first.span.to(parser.previous_span());
+	parser.expect_error(TokenType::InLoop, Some("Expected `in` after the loop variables".to_string()))?;
+	let sequence = parse_expr(parser, BindingPower::Default)?;
+	let body = parse_block_body(parser)?;
+	Ok(Expr::ForExpr { var_names, sequence: Box::new(sequence), body, label: None, span })
+}
+
+/// `while cond { }`. Its value is null.
+pub fn parse_while_expr(parser: &mut Parser) -> ParseResult<Expr> {
+	parser.expect(TokenType::WhileLoop)?;
+	let condition = parse_expr(parser, BindingPower::Default)?;
+	let body = parse_block_body(parser)?;
+	Ok(Expr::WhileExpr { condition: Box::new(condition), body, label: None })
+}
+
+/// `loop { }`, left only through `break`, whose value it takes.
+pub fn parse_loop_expr(parser: &mut Parser) -> ParseResult<Expr> {
+	parser.expect(TokenType::BreakLoop)?;
+	let body = parse_block_body(parser)?;
+	Ok(Expr::LoopExpr { body, label: None })
+}
+
+/// A loop named so that `break` and `continue` in nested loops can refer
+/// to it: `'outer: for x in xs { }`.
+pub fn parse_labelled_expr(parser: &mut Parser) -> ParseResult<Expr> {
+	let name = parse_label(parser)?;
+	parser.expect_error(TokenType::Colon, Some("Expected ':' after the label".to_string()))?;
+	let mut expr = match parser.current_tokenkind() {
+		TokenType::WhileLoop => parse_while_expr(parser)?,
+		TokenType::BreakLoop => parse_loop_expr(parser)?,
+		TokenType::ForLoop => parse_for_expr(parser)?,
+		_ => return Err(parser.throw(ErrorCode::UnexpectedToken, "Only loops can have a label")),
+	};
+	if let Expr::WhileExpr { label, .. } | Expr::LoopExpr { label, .. } | Expr::ForExpr { label, .. } = &mut expr {
+		*label = Some(name);
+	}
+	Ok(expr)
+}
+
+// `'name`, with the quote not yet consumed
+fn parse_label(parser: &mut Parser) -> ParseResult<String> {
+	parser.expect(TokenType::Quote)?;
+	Ok(parser.expect_error(TokenType::Identifier, Some("Expected a label name after '".to_string()))?.value)
+}
+
+// The optional `'label` after `break` or `continue`
+fn parse_jump_label(parser: &mut Parser) -> ParseResult<Option<String>> {
+	if parser.current_tokenkind() == TokenType::Quote {
+		return Ok(Some(parse_label(parser)?));
+	}
+	Ok(None)
+}
+
+pub fn parse_break_expr(parser: &mut Parser) -> ParseResult<Expr> {
+	let span = parser.expect(TokenType::Break)?.span;
+	let label = parse_jump_label(parser)?;
+	let value = if parser.is_one_of_many(vec![TokenType::NewLine, TokenType::SemiColon, TokenType::RBrace, TokenType::EOF]) {
+		None
+	} else {
+		Some(Box::new(parse_expr(parser, BindingPower::Default)?))
+	};
+	Ok(Expr::BreakExpr { label, value, span })
+}
+
+pub fn parse_continue_expr(parser: &mut Parser) -> ParseResult<Expr> {
+	let span = parser.expect(TokenType::Continue)?.span;
+	let label = parse_jump_label(parser)?;
+	Ok(Expr::ContinueExpr { label, span })
+}
+
+fn parse_block_body(parser: &mut Parser) -> ParseResult<Vec<Stmt>> {
+	Ok(parse_block(parser)?.extractblock_body().cloned().unwrap_or_default())
+}
+
+/// `lambda |x: Number, y| x + y` or `lambda |x| { .. }`. Calling it gives
+/// the value of its last statement, unless it returns earlier.
+pub fn parse_lambda_expr(parser: &mut Parser) -> ParseResult<Expr> {
+	parser.expect(TokenType::Lambda)?;
+	parser.expect(TokenType::Verbar)?;
+	let parameters = parse_parameters(parser, false)?;
+	let body = if parser.current_tokenkind() == TokenType::LBrace && !parser.at_map_literal() {
+		parse_block_body(parser)?
+	} else {
+		let checkpoint = parser.checkpoint();
+		let expr = parse_expr(parser, BindingPower::Default)?;
+		parser.wrap_node(checkpoint, SyntaxKind::ExpressionStmt);
+		vec![Stmt::new_expression(expr)]
+	};
+	let captures = captures(&parameters, &body);
+	Ok(Expr::Lambda { parameters, body, captures })
+}
+
+/// `[1, 2, 3]`, possibly spread over several lines and with a trailing comma.
+pub fn parse_array_expr(parser: &mut Parser) -> ParseResult<Expr> {
+	parser.expect(TokenType::LBracket)?;
+	let mut value = Vec::new();
+	parser.clean_newline();
+	while parser.has_token() && parser.current_tokenkind() != TokenType::RBracket {
+		value.push(parse_expr(parser, BindingPower::Comma)?);
+		parser.clean_newline();
+		if parser.current_tokenkind() != TokenType::RBracket {
+			parser.expect_error(TokenType::Comma, Some("Expected ',' or ']' after a list element".to_string()))?;
+			parser.clean_newline();
+		}
+	}
+	parser.expect_error(TokenType::RBracket, Some("Expected ']' to close the list".to_string()))?;
+	Ok(Expr::List { length: value.len(), value })
+}
+
+/// `{ "a": 1, b: 2 }`. A bare name as a key is a string, as in `b`.
+pub fn parse_map_expr(parser: &mut Parser) -> ParseResult<Expr> {
+	let open = parser.expect(TokenType::LBrace)?.span;
+	let mut entries = Vec::new();
+	parser.clean_newline();
+	while parser.has_token() && parser.current_tokenkind() != TokenType::RBrace {
+		let checkpoint = parser.checkpoint();
+		let key = if parser.current_tokenkind() == TokenType::Identifier || parser.current_tokenkind().is_contextual() {
+			let name = parser.advance().value;
+			Expr::String { length: name.chars().count(), value: name }
+		} else {
+			parse_expr(parser, BindingPower::Comma)?
+		};
+		parser.expect_error(TokenType::Colon, Some("Expected ':' after a map key".to_string()))?;
+		parser.clean_newline();
+		let value = parse_expr(parser, BindingPower::Comma)?;
+		parser.wrap_node(checkpoint, SyntaxKind::Property);
+		entries.push((key, value));
+		parser.clean_newline();
+		if parser.current_tokenkind() != TokenType::RBrace {
+			parser.expect_error(TokenType::Comma, Some("Expected ',' or '}' after a map entry".to_string()))?;
+			parser.clean_newline();
+		}
+	}
+	parser.expect_error(TokenType::RBrace, Some("Expected '}' to close the map".to_string()))
+		.map_err(|err| err.with_label(open, "map opened here"))?;
+	Ok(Expr::Map { entries })
+}
+
+/// `a.b` or `a[b]`. Both bind tighter than a call, and the Pratt loop
+/// keeps going after each, so `a.b[c](d).e` reads left to right.
+pub fn parse_member_expr(parser: &mut Parser, left: Expr, _bp: BindingPower) -> ParseResult<Expr> {
+	let member = Box::new(left);
+	if parser.advance().kind == TokenType::LBracket {
+		let open = parser.previous_span();
+		let property = parse_expr(parser, BindingPower::Default)?;
+		parser.expect_error(TokenType::RBracket, Some("Expected ']' to close the index".to_string()))
+			.map_err(|err| err.with_label(open, "index opened here"))?;
+		return Ok(Expr::ComputedExpr { member, property: Box::new(property) });
+	}
+	let name = parser.expect_error(TokenType::Identifier, Some("Expected a property name after '.'".to_string()))?;
+	Ok(Expr::MembreExpr { member, property: Box::new(Expr::Identifier { value: name.value, span: name.span }) })
+}
+
+/// `f(a, b)`, possibly with named arguments after the positional ones:
+/// `f(a, key: b)`. A call with named arguments is an `NCallExpr`. A list
+/// can be spread into positional arguments with `f(...xs)`.
+pub fn parse_call_expr(parser: &mut Parser, caller: Expr, _bp: BindingPower) -> ParseResult<Expr> {
+	let open = parser.expect(TokenType::LParen)?.span;
+	let mut args = Vec::new();
+	let mut named = false;
+	parser.clean_newline();
+	while parser.has_token() && parser.current_tokenkind() != TokenType::RParen {
+		let is_name = parser.current_tokenkind() == TokenType::Identifier || parser.current_tokenkind().is_contextual();
+		if is_name && parser.peek(1) == TokenType::Colon {
+			let checkpoint = parser.checkpoint();
+			let name = parser.advance().value;
+			parser.advance();
+			let value = parse_expr(parser, BindingPower::Comma)?;
+			parser.wrap_node(checkpoint, SyntaxKind::Property);
+			args.push(Expr::Property { name, value: Box::new(value) });
+			named = true;
+		} else if named {
+			return Err(parser.throw(ErrorCode::UnexpectedToken, "Positional arguments must come before named ones"));
+		} else if parser.current_tokenkind() == TokenType::DotDotDot {
+			let checkpoint = parser.checkpoint();
+			parser.advance();
+			let value = parse_expr(parser, BindingPower::Comma)?;
+			parser.wrap_node(checkpoint, SyntaxKind::SpreadExpr);
+			args.push(Expr::SpreadExpr { value: Box::new(value) });
+		} else {
+			args.push(parse_expr(parser, BindingPower::Comma)?);
+		}
+		parser.clean_newline();
+		if parser.current_tokenkind() != TokenType::RParen {
+			parser.expect_error(TokenType::Comma, Some("Expected ',' or ')' after an argument".to_string()))?;
+			parser.clean_newline();
+		}
+	}
+	parser.expect_error(TokenType::RParen, Some("Expected ')' to close the call".to_string()))
+		.map_err(|err| err.with_label(open, "call opened here"))?;
+	let span = caller.start_span().unwrap_or(open).to(parser.previous_span());
+	let caller = Box::new(caller);
+	if named {
+		Ok(Expr::NCallExpr { args, caller, span })
+	} else {
+		Ok(Expr::CallExpr { args, caller, span })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::parser::expression_ast::Expr;
+	use crate::parser::statement_ast::Stmt;
+
+	// The expression in `source` written out with its structure made explicit
+	fn parse(source: &str) -> String {
+		match crate::parse_expression(source) {
+			Ok(expr) => shape(&expr),
+			Err(errors) => panic!("{} does not parse: {}", source, errors[0]),
+		}
+	}
+
+	fn shape(expr: &Expr) -> String {
+		let all = |exprs: &[Expr]| exprs.iter().map(shape).collect::<Vec<_>>().join(" ");
+		let label = |label: &Option<String>| label.as_ref().map_or(String::new(), |l| format!("'{} ", l));
+		match expr {
+			Expr::Number { value } => value.to_string(),
+			Expr::String { value, .. } => format!("{:?}", value),
+			Expr::Boolean { value } => value.to_string(),
+			Expr::Null => "null".to_string(),
+			Expr::Identifier { value, .. } => value.clone(),
+			Expr::BinaryExpr { left, operator, right } => format!("({} {} {})", operator.value, shape(left), shape(right)),
+			Expr::UnaryExpr { operator, right } => format!("({} {})", operator.value, shape(right)),
+			Expr::AssignmentExpr { assigne, value } => format!("(= {} {})", shape(assigne), shape(value)),
+			Expr::CompoundAssignmentExpr { assigne, operator, value } => format!("({}= {} {})", operator.value, shape(assigne), shape(value)),
+			Expr::TernaryExpr { condition, true_value, false_value } => format!("(? {} {} {})", shape(condition), shape(true_value), shape(false_value)),
+			Expr::CallExpr { caller, args, .. } | Expr::NCallExpr { caller, args, .. } => format!("(call {} [{}])", shape(caller), all(args)),
+			Expr::Property { name, value } => format!("{}: {}", name, shape(value)),
+			Expr::SpreadExpr { value } => format!("...{}", shape(value)),
+			Expr::MembreExpr { member, property } => format!("(. {} {})", shape(member), shape(property)),
+			Expr::ComputedExpr { member, property } => format!("([] {} {})", shape(member), shape(property)),
+			Expr::List { value, .. } => format!("[{}]", all(value)),
+			Expr::Map { entries } => format!("{{{}}}", entries.iter().map(|(k, v)| format!("{}: {}", shape(k), shape(v))).collect::<Vec<_>>().join(", ")),
+			Expr::RangeExpr { start, end } => format!("(.. {} {})", shape(start), shape(end)),
+			Expr::TemplateString { parts } => format!("(template {})", all(parts)),
+			Expr::IfExpr { body, else_branch } => {
+				let branches: Vec<String> = body.iter().map(|(c, b)| format!("{} {}", shape(c), block(b))).collect();
+				format!("(if {} else {})", branches.join(" elif "), block(else_branch))
+			}
+			Expr::ForExpr { var_names, sequence, body, label: l, .. } => format!("({}for {} in {} {})", label(l), var_names.join(","), shape(sequence), block(body)),
+			Expr::WhileExpr { condition, body, label: l } => format!("({}while {} {})", label(l), shape(condition), block(body)),
+			Expr::LoopExpr { body, label: l } => format!("({}loop {})", label(l), block(body)),
+			Expr::BreakExpr { label: l, value, .. } => format!("(break {}{})", label(l), value.as_ref().map_or(String::new(), |v| shape(v))).replace(" )", ")"),
+			Expr::ContinueExpr { label: l, .. } => format!("(continue {})", label(l)).replace(" )", ")"),
+			Expr::Return { value } => format!("(return {})", shape(value)),
+			Expr::Lambda { parameters, body, captures } => {
+				let names: Vec<String> = parameters.iter().filter_map(|p| match p {
+					Stmt::Parameter { name, .. } => Some(name.clone()),
+					_ => None,
+				}).collect();
+				format!("(lambda |{}| {} captures [{}])", names.join(","), block(body), captures.join(","))
+			}
+			Expr::Error { .. } => "error".to_string(),
+		}
+	}
+
+	fn block(body: &[Stmt]) -> String {
+		let stmts: Vec<String> = body.iter().map(|stmt| match stmt {
+			Stmt::Expression { expression } => shape(expression),
+			Stmt::VariableDecl { name, value, .. } => format!("(var {} {})", name, shape(value)),
+			Stmt::FunctionDecl { name, .. } => format!("(func {})", name),
+			Stmt::Block { body } => block(body),
+			other => format!("{:?}", other),
+		}).collect();
+		format!("{{{}}}", stmts.join("; "))
+	}
+
+	#[test]
+	fn template_strings_alternate_text_and_expressions() {
+		assert_eq!(parse("`Hi ${name}, ${count + 1} items`"), r#"(template "Hi " name ", " (+ count 1) " items")"#);
+		assert_eq!(parse("`${a}${b}`"), "(template a b)");
+		assert_eq!(parse("``"), "(template )");
+	}
+
+	#[test]
+	fn an_unclosed_interpolation_is_a_syntax_error() {
+		let errors = crate::parse_expression("`open ${1").unwrap_err();
+		assert_eq!(errors[0].to_string(), "error[E0002] 1:10: Expected '}' to close the interpolation");
+	}
+
+	#[test]
+	fn template_strings_nest() {
+		assert_eq!(parse("`a ${ {1: `b ${c}`}[1] } d`"), r#"(template "a " ([] {1: (template "b " c)} 1) " d")"#);
+	}
+
+	#[test]
+	fn if_else_chains() {
+		assert_eq!(parse("if a { 1 } else if b { 2 } else { 3 }"), "(if a {1} elif b {2} else {3})");
+		assert_eq!(parse("if a { 1 }"), "(if a {1} else {})");
+		assert_eq!(parse("if a {\n    1\n}\nelse {\n    2\n}"), "(if a {1} else {2})");
+	}
+
+	#[test]
+	fn an_if_is_a_value() {
+		assert_eq!(parse("1 + if a { 2 } else { 3 }"), "(+ 1 (if a {2} else {3}))");
+		let program = crate::parse_program("?x: Number = if a { 1 } else { 2 }\n").unwrap();
+		let Stmt::Program { body } = program else { unreachable!() };
+		assert_eq!(block(&body), "{(var x (if a {1} else {2}))}");
+	}
+
+	#[test]
+	fn for_loops() {
+		assert_eq!(parse("for x in xs { print(x) }"), "(for x in xs {(call print [x])})");
+		assert_eq!(parse("for k, v in m { }"), "(for k,v in m {})");
+		assert_eq!(parse("for i in 0..10 { }"), "(for i in (.. 0 10) {})");
+	}
+
+	#[test]
+	fn a_for_loop_needs_in() {
+		let errors = crate::parse_expression("for x xs { }").unwrap_err();
+		assert!(errors[0].to_string().ends_with("Expected `in` after the loop variables"), "{}", errors[0]);
+	}
+
+	#[test]
+	fn loops_and_jumps() {
+		assert_eq!(parse("while x < 3 { continue }"), "(while (< x 3) {(continue)})");
+		assert_eq!(parse("loop { break 1 + 2 }"), "(loop {(break (+ 1 2))})");
+		assert_eq!(parse("'outer: loop {\n    while true { break 'outer }\n}"), "('outer loop {(while true {(break 'outer)})})");
+		assert_eq!(parse("'rows: for r in rows { continue 'rows }"), "('rows for r in rows {(continue 'rows)})");
+	}
+
+	#[test]
+	fn lambdas_take_an_expression_or_a_block() {
+		assert_eq!(parse("lambda |x: Number, y| x + y"), "(lambda |x,y| {(+ x y)} captures [])");
+		assert_eq!(parse("lambda || {\n    ?n: Number = 1\n    n + k\n}"), "(lambda || {(var n 1); (+ n k)} captures [k])");
+		assert_eq!(parse("lambda |x| {x: 1}"), r#"(lambda |x| {{"x": 1}} captures [])"#);
+	}
+
+	#[test]
+	fn ternaries_nest_to_the_right() {
+		assert_eq!(parse("a ? b : c ? d : e"), "(? a b (? c d e))");
+		assert_eq!(parse("a ? b ? c : d : e"), "(? a (? b c d) e)");
+	}
+
+	#[test]
+	fn a_ternary_binds_looser_than_or_and_tighter_than_assignment() {
+		assert_eq!(parse("x = a or b ? c : d"), "(= x (? (or a b) c d))");
+		assert_eq!(parse("a == 1 ? b + 1 : c"), "(? (== a 1) (+ b 1) c)");
+	}
+
+	#[test]
+	fn a_question_mark_starting_a_line_declares_a_variable() {
+		let program = crate::parse_program("?x: Number = a ? 1 : 2\n").unwrap();
+		let Stmt::Program { body } = program else { unreachable!() };
+		assert_eq!(block(&body), "{(var x (? a 1 2))}");
+	}
+
+	#[test]
+	fn members_indexes_and_calls_chain_left_to_right() {
+		assert_eq!(parse("a.b[c](d).e"), "(. (call ([] (. a b) c) [d]) e)");
+		assert_eq!(parse("f(x)(y)[0]"), "([] (call (call f [x]) [y]) 0)");
+	}
+
+	#[test]
+	fn postfix_chains_bind_tighter_than_operators() {
+		assert_eq!(parse("-a.b"), "(- (. a b))");
+		assert_eq!(parse("a + b.c * d[0]"), "(+ a (* (. b c) ([] d 0)))");
 	}
-	left
 }
 
@@ -1,100 +1,204 @@
 use crate::parser::parser::Parser;
 use crate::parser::statement_ast::*;
+use crate::parser::expression_ast::Expr;
+use crate::parser::parse_error::*;
 use crate::lexer::token::TokenType;
 use crate::parser::lookup::*;
 use crate::parser::parse_expression::*;
+use crate::parser::cst::SyntaxKind;
 
-pub fn parse_stmt(parser: &mut Parser) -> Stmt{
-    let func = get_stmt_handler(parser.current_tokenkind());
-    if func.is_some() {
-        return  (func.unwrap())(parser)
+pub fn parse_stmt(parser: &mut Parser) -> ParseResult<Stmt> {
+    match get_stmt_handler(parser.current_tokenkind()) {
+        Some(func) => func(parser),
+        None => parse_expr_stmt(parser),
     }
-    return parse_expr_stmt(parser)
 }
 
-pub fn  parse_expr_stmt(parser: &mut Parser) -> Stmt {
-    let expr = parse_expr(parser, BindingPower::Default);
-    parser.is_one_of_many(vec!(TokenType::NewLine, TokenType::EOF));
-    Stmt::new_expression(expr)
+pub fn  parse_expr_stmt(parser: &mut Parser) -> ParseResult<Stmt> {
+    let expr = parse_expr(parser, BindingPower::Default)?;
+    if parser.current_tokenkind() == TokenType::SemiColon {
+        parser.advance();
+    }
+    if !parser.is_one_of_many(vec!(TokenType::NewLine, TokenType::EOF, TokenType::RBrace)) {
+        let found = format!("Expected the end of the statement but received {:?}", parser.current_tokenkind());
+        return Err(parser.throw(ErrorCode::UnexpectedToken, &found));
+    }
+    Ok(Stmt::new_expression(expr))
+}
+
+pub fn parse_block(parser: &mut Parser) -> ParseResult<Stmt> {
+    parser.nested(|parser| {
+        let checkpoint = parser.checkpoint();
+        let open = parser.expect(TokenType::LBrace)?.span;
+        let mut body = Vec::new();
+        parser.clean_newline();
+        while parser.has_token() && parser.current_tokenkind() != TokenType::RBrace{
+            body.push(parser.parse_stmt_recovering());
+            parser.clean_newline();
+        }
+        parser.expect_error(TokenType::RBrace, Some("Expected '}' to close the block".to_string()))
+            .map_err(|err| err.with_label(open, "block opened here"))?;
+        parser.wrap_node(checkpoint, SyntaxKind::Block);
+        Ok(Stmt::new_block(body))
+    })
 }
 
-pub fn parse_block(parser: &mut Parser) -> Stmt {
-    parser.expect(TokenType::LBrace);
-    let mut body = Vec::new();
-    while parser.has_token() && parser.current_tokenkind() != TokenType::RBrace{
-        body.push(parse_stmt(parser));
+// A `{` starting a statement opens a block, unless it reads as a map
+pub fn parse_block_stmt(parser: &mut Parser) -> ParseResult<Stmt> {
+    if parser.at_map_literal() {
+        return parse_expr_stmt(parser);
     }
-    parser.expect(TokenType::RBrace);
-    Stmt::new_block(body)
+    parse_block(parser)
 }
 
-pub fn parse_var(parser: &mut Parser) -> Stmt{
+pub fn parse_var(parser: &mut Parser) -> ParseResult<Stmt> {
+    let doc = parser.doc_comment();
     let constant = parser.advance().kind == TokenType::ExclamationMark;
-    let name = parser.expect_error(TokenType::Identifier, Some("Expected Identifier".to_owned())).value;
-    parser.expect(TokenType::Colon);
-    let explicite_type = parse_type(parser, BindingPower::Default);
-    parser.expect_error(TokenType::Equal, Some("Expected an equals sign".to_string()));
-    let value = parse_expr(parser, BindingPower::Assignment);
-    Stmt::new_variable_decl(
-        name,
+    let name = parser.expect_error(TokenType::Identifier, Some("Expected Identifier".to_owned()))?;
+    parser.expect(TokenType::Colon)?;
+    let explicite_type = parse_type(parser)?;
+    parser.expect_error(TokenType::Equal, Some("Expected an equals sign".to_string()))?;
+    // Keep the declaration even if its value is malformed
+    let start = parser.pos;
+    let span = parser.current_span();
+    let value = match parse_expr(parser, BindingPower::Assignment) {
+        Ok(n) => n,
+        Err(err) => {
+            parser.recover(err, start);
+            Expr::Error { span: span.to(parser.previous_span()) }
+        }
+    };
+    Ok(Stmt::new_variable_decl(
+        name.value,
+        name.span,
         value,
         constant,
         explicite_type
-    )
+    ).with_doc(doc))
 }
 
-pub fn parse_funct(parser: &mut Parser) -> Stmt {
+pub fn parse_funct(parser: &mut Parser) -> ParseResult<Stmt> {
+    let doc = parser.doc_comment();
     parser.advance();
-    let name = parser.expect_error(TokenType::Identifier, Some("Expect an identifier".to_string())).value;
-    parser.expect(TokenType::Verbar);
-    let param = parse_parameter(parser);
-    let retype;
-    if parser.current_tokenkind() == TokenType::Colon {
+    let name = parser.expect_error(TokenType::Identifier, Some("Expect an identifier".to_string()))?;
+    parser.expect(TokenType::Verbar)?;
+    let param = parse_parameters(parser, true)?;
+    let retype = if parser.current_tokenkind() == TokenType::Colon {
         parser.advance();
-        if parser.current_tokenkind() == TokenType::RBracket {
-            retype = parse_type(parser, BindingPower::Member);
-        } else {
-            retype = parse_type(parser, BindingPower::Primary); 
-        }
+        parse_type(parser)?
     } else {
-		retype = None;
-	}
-    
+		Type::Null
+	};
 
-    let body = parse_block(parser).extractblock_body();
-	Stmt::new_function_decl(
-		name,
+    let body = parse_block(parser)?.extractblock_body().cloned();
+	Ok(Stmt::new_function_decl(
+		name.value,
+		name.span,
 		param,
 		 retype,
-		body.cloned()
-    )
+		body
+    ).with_doc(doc))
 }
 
-pub fn parse_parameter(parser: &mut Parser) -> Vec<Stmt> {
-    let param = Vec::new();
-    while parser.has_token() && parser.current_tokenkind() != TokenType::RParen {
-        let name = parser.expect(TokenType::Identifier).value;
+// Parameters of a function, after the opening `|` and up to the closing one.
+// Unless `typed`, a parameter may leave out its type, which is then `Any`.
+// The last parameter may be a rest parameter, `...name: [T]`.
+pub fn parse_parameters(parser: &mut Parser, typed: bool) -> ParseResult<Vec<Stmt>> {
+    let mut param = Vec::new();
+    while parser.has_token() && parser.current_tokenkind() != TokenType::Verbar {
+        let checkpoint = parser.checkpoint();
+        let start = parser.current_span();
+        let rest = parser.current_tokenkind() == TokenType::DotDotDot;
+        if rest {
+            parser.advance();
+        }
+        let name = parser.expect(TokenType::Identifier)?;
 
-        let ptype: Option<Type>;
-        if parser.has_token() && parser.current_tokenkind() != TokenType::Colon {
+        let ptype = if parser.current_tokenkind() == TokenType::Colon {
+            parser.advance();
+            parse_type(parser)?
+        } else if typed {
+            return Err(parser.throw(ErrorCode::ExpectedType, "Expected type description"));
+        } else if rest {
+            Type::List(Box::new(Type::Any))
+        } else {
+            Type::Any
+        };
+        if rest && !matches!(ptype, Type::List(_)) {
+            let span = start.to(parser.previous_span());
+            return Err(ParseError::new(ErrorCode::ExpectedType, "A rest parameter is a list, e.g. `...rest: [Number]`", TokenType::DotDotDot, span));
+        }
+        // Parsed above assignment: the `=` of a default ends with the value
+        let default = if parser.current_tokenkind() == TokenType::Equal {
             parser.advance();
-            if parser.current_tokenkind() == TokenType::LBracket {
-                ptype = parse_type(parser, BindingPower::Member);
-            } else {
-                ptype = parse_type(parser, BindingPower::Primary);
-            }
+            Some(parse_expr(parser, BindingPower::Assignment)?)
         } else {
-            parser.throw("Expected type description");
+            None
+        };
+        if rest && default.is_some() {
+            let span = start.to(parser.previous_span());
+            return Err(ParseError::new(ErrorCode::UnexpectedToken, "A rest parameter cannot have a default value", TokenType::DotDotDot, span));
         }
-        if parser.is_one_of_many(vec![TokenType::EOF, TokenType::RParen]){
-            parser.expect(TokenType::Comma);
+        if rest && parser.current_tokenkind() != TokenType::Verbar {
+            let span = start.to(parser.previous_span());
+            return Err(ParseError::new(ErrorCode::UnexpectedToken, "The rest parameter must be the last one", TokenType::DotDotDot, span));
         }
+        if !parser.is_one_of_many(vec![TokenType::EOF, TokenType::Verbar]){
+            parser.expect(TokenType::Comma)?;
+        }
+        parser.wrap_node(checkpoint, SyntaxKind::Parameter);
         param.push(
             Stmt::new_parameter(
-                name,
-                ptype.unwrap())
+                name.value,
+                name.span,
+                ptype).with_default(default).with_rest(rest)
         );
     }
-    parser.expect(TokenType::RParen);
-    param
-}
\ No newline at end of file
+    parser.expect(TokenType::Verbar)?;
+    Ok(param)
+}
+
+// Type annotation, e.g. `Number`, `Point` or `[String]`
+pub fn parse_type(parser: &mut Parser) -> ParseResult<Type> {
+    parser.nested(|parser| {
+        if parser.current_tokenkind() == TokenType::LBracket {
+            parser.advance();
+            let inner = parse_type(parser)?;
+            parser.expect_error(TokenType::RBracket, Some("Expected ']' to close the list type".to_string()))?;
+            return Ok(Type::List(Box::new(inner)));
+        }
+        if parser.current_tokenkind() == TokenType::Null {
+            parser.advance();
+            return Ok(Type::Null);
+        }
+        if parser.current_tokenkind() != TokenType::Identifier {
+            return Err(parser.throw(ErrorCode::ExpectedType, "Expected type description"));
+        }
+        let name = parser.advance().value;
+        Ok(match name.as_str() {
+            "Number" => Type::Number,
+            "String" => Type::String,
+            "Boolean" => Type::Boolean,
+            "Any" => Type::Any,
+            _ => Type::Custom(name),
+        })
+    })
+}
+
+pub fn parse_return_stmt(parser: &mut Parser) -> ParseResult<Stmt> {
+    let value = parse_return_decl(parser)?;
+    Ok(Stmt::new_expression(value))
+}
+
+// An `if` is an expression, so it can also be used as a value
+pub fn parse_if_stmt(parser: &mut Parser) -> ParseResult<Stmt> {
+    parse_expr_stmt(parser)
+}
+
+pub fn parse_for_stmt(parser: &mut Parser) -> ParseResult<Stmt> {
+    parse_expr_stmt(parser)
+}
+
+pub fn parse_while_stmt(parser: &mut Parser) -> ParseResult<Stmt> {
+    parse_expr_stmt(parser)
+}
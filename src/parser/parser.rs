@@ -1,47 +1,102 @@
 use crate::parser::statement_ast::*;
-use crate::parser::parse_Statement::*;
+use crate::parser::parse_statement::*;
+use crate::parser::parse_error::*;
+use crate::parser::lookup::is_stmt_token;
+use crate::parser::cst::{Event, SyntaxKind};
 use crate::lexer::token::*;
 
+/// How deeply expressions, blocks and types may nest. Deeper input is
+/// reported as an error rather than overflowing the stack.
+pub const MAX_DEPTH: usize = 200;
+
 pub struct Parser {
     pub tokens: Vec<Token>,
     pub pos: usize,
+    pub errors: Vec<ParseError>, // Errors recovered from so far
+    pub events: Vec<Event>,      // Tree shape, for building the concrete syntax tree
+    depth: usize,                // Nesting of the construct being parsed
 }
 
 impl Parser {
-    pub fn new(tokens: Vec<Token>) -> Self {
+    pub fn new(mut tokens: Vec<Token>) -> Self {
         //createTokenLookup();
         //createTypeTokenLookup();
-        Parser {pos: 0, tokens: tokens}
+        // Always end on EOF so looking past the input never fails
+        if tokens.last().is_none_or(|t| t.kind != TokenType::EOF) {
+            let end = tokens.last().map(|t| t.span).unwrap_or_default();
+            tokens.push(Token::with_span(TokenType::EOF, "", Span { start: end.end, ..end }));
+        }
+        Parser {pos: 0, tokens, errors: Vec::new(), events: Vec::new(), depth: 0}
     }
     
-    pub fn is_one_of_many(&mut self, tokens: Vec<TokenType>) -> bool {
-        for token in tokens{
-            if token == self.current_tokenkind(){
-                return true
-            }
+    /// Run `parse` one level of nesting deeper.
+    pub fn nested<T>(&mut self, parse: impl FnOnce(&mut Parser) -> ParseResult<T>) -> ParseResult<T> {
+        if self.depth >= MAX_DEPTH {
+            let message = format!("The code is nested more than {} levels deep", MAX_DEPTH);
+            return Err(self.throw(ErrorCode::TooDeep, &message));
         }
-        return false
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
     }
 
-    pub fn expect_error(&mut self, expected: TokenType, err: Option<String>) -> Token {
+    pub fn is_one_of_many(&mut self, tokens: Vec<TokenType>) -> bool {
+        let current = self.current_tokenkind();
+        tokens.contains(&current)
+    }
+
+    pub fn expect_error(&mut self, expected: TokenType, err: Option<String>) -> ParseResult<Token> {
+        // Unreadable input was already reported by the lexer
+        while self.current_tokenkind() == TokenType::Error {
+            self.advance();
+        }
+        // Weak keywords are plain identifiers outside their own construct
+        if expected == TokenType::Identifier && self.current_tokenkind().is_contextual() {
+            let mut token = self.advance();
+            token.kind = TokenType::Identifier;
+            return Ok(token);
+        }
         if self.current_tokenkind() != expected {
-            match err {
-                Some(n) => panic!("ParsingError {}", n),
-                None => panic!("{}", format!("Expected {:#?} but recieved {:#?}", expected, self.current_tokenkind())),
-            }
+            let span = self.current_span();
+            return Err(ParseError::expected(expected, self.current_tokenkind(), span, err));
         }
-        self.advance()
+        Ok(self.advance())
     }
 
-    pub fn expect(&mut self, expected: TokenType) -> Token {
+    pub fn expect(&mut self, expected: TokenType) -> ParseResult<Token> {
         self.expect_error(expected, None)
     }
 
     pub fn current_tokenkind(&mut self) -> TokenType {
-        match self.tokens.get(self.pos) {
-            Some(n) => return n.kind,
-            None => panic!("No token found at index {}", self.pos)
-        }
+        self.current_token().kind
+    }
+
+    /// Kind of the first token at or after the current one that is not a
+    /// NewLine, without consuming anything.
+    pub fn peek_past_newlines(&self) -> TokenType {
+        self.tokens[self.pos.min(self.tokens.len() - 1)..].iter()
+            .find(|t| t.kind != TokenType::NewLine)
+            .map_or(TokenType::EOF, |t| t.kind)
+    }
+
+    /// Kind of the token `offset` places after the current one.
+    pub fn peek(&self, offset: usize) -> TokenType {
+        let last = self.tokens.len() - 1;
+        self.tokens[(self.pos + offset).min(last)].kind
+    }
+
+    /// Whether the `{` at the current token opens a map literal rather
+    /// than a block, i.e. its first entry is a name, string or number
+    /// followed by `:`. An empty `{}` is left to the caller.
+    pub fn at_map_literal(&self) -> bool {
+        let mut rest = self.tokens[self.pos.min(self.tokens.len() - 1)..].iter()
+            .map(|t| t.kind)
+            .filter(|&k| k != TokenType::NewLine);
+        let (open, key, colon) = (rest.next(), rest.next(), rest.next());
+        open == Some(TokenType::LBrace)
+            && key.is_some_and(|k| matches!(k, TokenType::Identifier | TokenType::String | TokenType::Number) || k.is_contextual())
+            && colon == Some(TokenType::Colon)
     }
 
     pub fn clean_newline(&mut self){
@@ -50,37 +105,217 @@ impl Parser {
         }
     }
 
+    /// Current token, or the final EOF once the input is exhausted.
     pub fn current_token(&mut self) -> &Token {
-        match self.tokens.get(self.pos) {
-            Some(n) => n,
-            None => panic!("No Current Token")
-        }
+        let last = self.tokens.len() - 1;
+        &self.tokens[self.pos.min(last)]
     }
 
     pub fn advance(&mut self) -> Token{
         let token = self.current_token().clone();
-        self.pos += 1;
+        if self.pos < self.tokens.len() {
+            self.pos += 1;
+            self.events.push(Event::Token);
+        }
         token
     }
 
+    /// Mark the start of a syntax node whose kind is known once parsed.
+    pub fn checkpoint(&mut self) -> usize {
+        self.events.push(Event::Tombstone);
+        self.events.len() - 1
+    }
+
+    /// Close a node of `kind` holding everything parsed since `checkpoint`.
+    /// A node already wrapped there ends up inside the new one.
+    pub fn wrap_node(&mut self, checkpoint: usize, kind: SyntaxKind) {
+        let inner = match self.events[checkpoint] {
+            Event::Tombstone => None,
+            start => {
+                self.events.push(start);
+                Some(self.events.len() - 1)
+            }
+        };
+        self.events[checkpoint] = Event::Start { kind, inner };
+        self.events.push(Event::Finish);
+    }
+
+    /// Doc comments written on their own lines before the current token,
+    /// including those separated from it by blank lines. A `///` after
+    /// code on the same line documents nothing.
+    pub fn doc_comment(&self) -> Option<String> {
+        let mut docs = Vec::new();
+        let mut i = self.pos.min(self.tokens.len() - 1);
+        loop {
+            // A comment is trivia of the line break ending its line
+            let starts_line = i == 0 || self.tokens[i - 1].kind == TokenType::NewLine;
+            if starts_line {
+                docs.extend(self.tokens[i].doc_comment());
+            }
+            if i == 0 || !starts_line {
+                break;
+            }
+            i -= 1;
+        }
+        if docs.is_empty() {
+            return None;
+        }
+        docs.reverse();
+        Some(docs.join("\n"))
+    }
+
+    /// Span of the token about to be consumed.
+    pub fn current_span(&mut self) -> Span {
+        self.current_token().span
+    }
+
+    /// Span of the last consumed token, used to close the span of a node.
+    pub fn previous_span(&self) -> Span {
+        match self.pos.checked_sub(1).and_then(|i| self.tokens.get(i)) {
+            Some(n) => n.span,
+            None => Span::default(),
+        }
+    }
+
     pub fn has_token(&mut self) -> bool {
         self.pos < self.tokens.len() && self.current_tokenkind() != TokenType::EOF
     }
 
-    pub fn throw(&mut self, mess: &str){
-        panic!("Parsing: {}", mess);
+    /// Build an error located at the current token.
+    pub fn throw(&mut self, code: ErrorCode, mess: &str) -> ParseError {
+        let span = self.current_span();
+        ParseError::new(code, mess, self.current_tokenkind(), span)
+    }
+
+    /// Record `err` and skip ahead to a point where parsing can resume.
+    /// `start` is the position of the statement being parsed; at least
+    /// one token past it is always skipped so the parser makes progress.
+    pub fn recover(&mut self, err: ParseError, start: usize) {
+        self.errors.push(err);
+        if self.pos == start && self.current_tokenkind() != TokenType::EOF {
+            self.advance();
+        }
+        // Braces opened while skipping are skipped up to their match
+        let mut depth = 0;
+        loop {
+            match self.current_tokenkind() {
+                TokenType::EOF => return,
+                TokenType::LBrace => depth += 1,
+                TokenType::RBrace if depth > 0 => depth -= 1,
+                _ if depth > 0 => (),
+                TokenType::NewLine => {
+                    self.advance();
+                    return;
+                }
+                TokenType::RBrace => return,
+                kind if is_stmt_token(kind) => return,
+                _ => (),
+            }
+            self.advance();
+        }
     }
 
-    pub fn parse(&mut self) -> Stmt {
+    /// Parse a statement, replacing it with `Stmt::Error` when it fails.
+    pub fn parse_stmt_recovering(&mut self) -> Stmt {
+        let start = self.pos;
+        let checkpoint = self.checkpoint();
+        let span = self.current_span();
+        let stmt = match parse_stmt(self) {
+            Ok(stmt) => stmt,
+            Err(err) => {
+                self.recover(err, start);
+                Stmt::Error { span: span.to(self.previous_span()) }
+            }
+        };
+        self.wrap_node(checkpoint, SyntaxKind::of_stmt(&stmt));
+        stmt
+    }
+
+    /// Parse the whole input, reporting every syntax error found along the
+    /// way. The returned program holds `Error` nodes where parsing failed.
+    pub fn parse_recovering(&mut self) -> (Stmt, Vec<ParseError>) {
         let mut program = Stmt::init_program();
+        self.clean_newline();
         while self.has_token() {
-            program.push_to_program(parse_stmt(self));
+            let stmt = self.parse_stmt_recovering();
+            let _ = program.push_to_program(stmt);
+            self.clean_newline();
+        }
+        (program, std::mem::take(&mut self.errors))
+    }
 
-            while self.current_tokenkind() == TokenType::NewLine{
-                self.advance();
-            }
+    pub fn parse(&mut self) -> Result<Stmt, Vec<ParseError>> {
+        let (program, errors) = self.parse_recovering();
+        if errors.is_empty() {
+            Ok(program)
+        } else {
+            Err(errors)
         }
-        program
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::parser::statement_ast::Stmt;
+
+    // Codes of the errors found in `source`, along with their lines
+    fn errors(source: &str) -> Vec<(&'static str, usize)> {
+        let errors = crate::parse_program(source).expect_err("the source has syntax errors");
+        errors.iter().map(|err| match err {
+            Error::Parse(err) => (err.code.as_str(), err.span.line),
+            Error::Lex(err) => (err.kind.code(), err.span.line),
+            other => panic!("unexpected error {}", other),
+        }).collect()
+    }
+
+    #[test]
+    fn every_bad_statement_is_reported() {
+        let source = "?a: Number = 1\n?b: = 2\nprint(a)\n?c Number = 3\n";
+        assert_eq!(errors(source), vec![("E0004", 2), ("E0001", 4)]);
+    }
+
+    #[test]
+    fn recovery_resumes_after_a_block() {
+        let source = "func f|| {\n    ?x: Number = )\n}\n?y: = 1\n";
+        assert_eq!(errors(source).iter().map(|e| e.1).collect::<Vec<_>>(), vec![2, 4]);
+    }
+
+    #[test]
+    fn lexical_errors_come_first() {
+        let source = "?a: = 1\nprint(2 $ 3)\n";
+        assert_eq!(errors(source), vec![("E0101", 2), ("E0004", 1)]);
+    }
+
+    // Documentation attached to the declaration `name` in `source`
+    fn doc(source: &str, name: &str) -> Option<String> {
+        let Ok(Stmt::Program { body }) = crate::parse_program(source) else { panic!("{} does not parse", source) };
+        body.into_iter().find_map(|stmt| match stmt {
+            Stmt::FunctionDecl { name: n, doc, .. } | Stmt::VariableDecl { name: n, doc, .. } if n == name => Some(doc),
+            _ => None,
+        }).expect("no such declaration")
+    }
+
+    #[test]
+    fn doc_comments_document_the_next_declaration() {
+        assert_eq!(doc("/// Adds.\n/// Twice.\nfunc f|| {}\n", "f").as_deref(), Some("Adds.\nTwice."));
+        assert_eq!(doc("print(1)\n    /// Indented.\n\n?x: Number = 1\n", "x").as_deref(), Some("Indented."));
+    }
+
+    #[test]
+    fn trailing_doc_comments_document_nothing() {
+        assert_eq!(doc("print(1) /// trailing\nfunc h|| {}\n", "h"), None);
+        assert_eq!(doc("/// Kept.\nprint(1) /// trailing\nfunc h|| {}\n", "h"), None);
+        assert_eq!(doc("print(1) /// trailing\n/// Own line.\nfunc h|| {}\n", "h").as_deref(), Some("Own line."));
+    }
+
+    #[test]
+    fn failed_statements_become_error_nodes() {
+        let mut parser = crate::parser::parser::Parser::new(crate::lex("?a: = 1\nprint(1)\n").unwrap());
+        let (program, errors) = parser.parse_recovering();
+        assert_eq!(errors.len(), 1);
+        let Stmt::Program { body } = program else { panic!("not a program") };
+        assert!(matches!(body.as_slice(), [Stmt::Error { .. }, Stmt::Expression { .. }]));
+    }
+}
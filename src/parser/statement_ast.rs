@@ -1,4 +1,5 @@
 use crate::parser::expression_ast::Expr;
+use crate::lexer::token::Span;
 
 
 // First, define the Type type (assuming it's needed)
@@ -9,6 +10,7 @@ pub enum Type {
     Boolean,
     Null,
     Any,
+    List(Box<Type>),
     Custom(String),
     // Add other types as needed
 }
@@ -30,24 +32,74 @@ pub enum Stmt {
         parameters: Vec<Stmt>,
         return_type: Type,
         body: Option<Vec<Stmt>>,
+        doc: Option<String>, // Text of the /// comments above the declaration
+        arity: Arity,        // How many positional arguments it accepts
+        span: Span,          // The name of the function
     },
     Parameter {
         name: String,
         param_type: Type,
+        default: Option<Box<Expr>>, // Used when a call leaves the argument out
+        rest: bool,                 // `...name: [T]`, gathering the extra arguments
+        span: Span,                 // The name of the parameter
     },
     VariableDecl {
         name: String,
         value: Box<Expr>,
         constant: bool,
         explicit_type: Type,
+        doc: Option<String>, // Text of the /// comments above the declaration
+        span: Span,          // The name of the variable
+    },
+    Error {
+        span: Span, // Source of a statement that failed to parse
     },
 }
 
 // Define Arity as a separate enum to handle both single number and range
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Arity {
     Exact(usize),
     Range(usize, usize),
+    AtLeast(usize), // With a rest parameter
+}
+
+impl Arity {
+    /// Arity of a function with `required` parameters, `optional` ones
+    /// with a default, and maybe a rest parameter.
+    pub fn new(required: usize, optional: usize, rest: bool) -> Self {
+        match (optional, rest) {
+            (_, true) => Arity::AtLeast(required),
+            (0, false) => Arity::Exact(required),
+            (_, false) => Arity::Range(required, required + optional),
+        }
+    }
+
+    /// Arity of a function declared with these `Stmt::Parameter`s.
+    pub fn of(parameters: &[Stmt]) -> Self {
+        let (mut required, mut optional, mut rest) = (0, 0, false);
+        for param in parameters {
+            match param {
+                Stmt::Parameter { rest: true, .. } => rest = true,
+                Stmt::Parameter { default: Some(_), .. } => optional += 1,
+                Stmt::Parameter { .. } => required += 1,
+                _ => (),
+            }
+        }
+        Arity::new(required, optional, rest)
+    }
+}
+
+impl std::fmt::Display for Arity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Arity::Exact(1) => write!(f, "1 argument"),
+            Arity::Exact(n) => write!(f, "{} arguments", n),
+            Arity::Range(min, max) => write!(f, "{} to {} arguments", min, max),
+            Arity::AtLeast(1) => write!(f, "at least 1 argument"),
+            Arity::AtLeast(n) => write!(f, "at least {} arguments", n),
+        }
+    }
 }
 
 // The ExpectStmt function (simplified since Rust has a strong type system)
@@ -87,27 +139,51 @@ impl Stmt {
 
     pub fn new_function_decl(
         name: String,
+        span: Span,
         parameters: Vec<Stmt>,
         return_type: Type,
         body: Option<Vec<Stmt>>,
     ) -> Self {
         Stmt::FunctionDecl {
             name,
+            arity: Arity::of(&parameters),
             parameters,
             return_type,
             body,
+            doc: None,
+            span,
         }
     }
 
-    pub fn new_parameter(name: String, param_type: Type) -> Self {
+    pub fn new_parameter(name: String, span: Span, param_type: Type) -> Self {
         Stmt::Parameter {
             name,
             param_type,
+            default: None,
+            rest: false,
+            span,
+        }
+    }
+
+    /// Make a parameter the rest parameter of its function.
+    pub fn with_rest(mut self, is_rest: bool) -> Self {
+        if let Stmt::Parameter { rest, .. } = &mut self {
+            *rest = is_rest;
         }
+        self
+    }
+
+    /// Give a parameter a default value.
+    pub fn with_default(mut self, value: Option<Expr>) -> Self {
+        if let Stmt::Parameter { default, .. } = &mut self {
+            *default = value.map(Box::new);
+        }
+        self
     }
 
     pub fn new_variable_decl(
         name: String,
+        span: Span,
         value: Expr,
         constant: bool,
         explicit_type: Type,
@@ -117,7 +193,17 @@ impl Stmt {
             value: Box::new(value),
             constant,
             explicit_type,
+            doc: None,
+            span,
+        }
+    }
+
+    /// Attach documentation to a function or variable declaration.
+    pub fn with_doc(mut self, text: Option<String>) -> Self {
+        if let Stmt::FunctionDecl { doc, .. } | Stmt::VariableDecl { doc, .. } = &mut self {
+            *doc = text;
         }
+        self
     }
     
     pub fn extractblock_body(&self) -> Option<&Vec<Stmt>> {